@@ -0,0 +1,111 @@
+#![no_main]
+
+//Runs the same reads through Buffer and through BufRead's default methods on a plain slice. Both
+//have to return the same values and fail on the same read.
+use arbitrary::{Arbitrary, Unstructured};
+use extended_primitives::buffer::Ascii;
+use extended_primitives::{BufRead, Buffer};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Read {
+    U8,
+    U16Be,
+    U24,
+    U32,
+    U48Be,
+    U64,
+    U128Be,
+    I24Be,
+    I48,
+    I128,
+    U256,
+    U256Be,
+    F32,
+    F64Be,
+    Bool,
+    VarInt,
+    VarInt2,
+    Uleb128,
+    Uleb128U128,
+    Sleb128,
+    Sleb128I128,
+    Zigzag,
+    ZigzagI128,
+    Hash,
+    Bytes(u16),
+    VarBytes,
+    String(u16),
+    VarString,
+    VarStringAscii,
+    CString(u16),
+    Vec,
+    Map,
+    Set(bool),
+}
+
+macro_rules! reads {
+    ($reader:expr, $op:expr) => {
+        match *$op {
+            Read::U8 => format!("{:?}", $reader.read_u8()),
+            Read::U16Be => format!("{:?}", $reader.read_u16_be()),
+            Read::U24 => format!("{:?}", $reader.read_u24()),
+            Read::U32 => format!("{:?}", $reader.read_u32()),
+            Read::U48Be => format!("{:?}", $reader.read_u48_be()),
+            Read::U64 => format!("{:?}", $reader.read_u64()),
+            Read::U128Be => format!("{:?}", $reader.read_u128_be()),
+            Read::I24Be => format!("{:?}", $reader.read_i24_be()),
+            Read::I48 => format!("{:?}", $reader.read_i48()),
+            Read::I128 => format!("{:?}", $reader.read_i128()),
+            Read::U256 => format!("{:?}", $reader.read_u256()),
+            Read::U256Be => format!("{:?}", $reader.read_u256_be()),
+            Read::F32 => format!("{:?}", $reader.read_f32().map(f32::to_bits)),
+            Read::F64Be => format!("{:?}", $reader.read_f64_be().map(f64::to_bits)),
+            Read::Bool => format!("{:?}", $reader.read_bool()),
+            Read::VarInt => format!("{:?}", $reader.read_varint()),
+            Read::VarInt2 => format!("{:?}", $reader.read_varint2()),
+            Read::Uleb128 => format!("{:?}", $reader.read_uleb128()),
+            Read::Uleb128U128 => format!("{:?}", $reader.read_uleb128_u128()),
+            Read::Sleb128 => format!("{:?}", $reader.read_sleb128()),
+            Read::Sleb128I128 => format!("{:?}", $reader.read_sleb128_i128()),
+            Read::Zigzag => format!("{:?}", $reader.read_zigzag()),
+            Read::ZigzagI128 => format!("{:?}", $reader.read_zigzag_i128()),
+            Read::Hash => format!("{:?}", $reader.read_hash()),
+            Read::Bytes(size) => format!("{:?}", $reader.read_bytes(size as usize)),
+            Read::VarBytes => format!("{:?}", $reader.read_var_bytes()),
+            Read::String(size) => format!("{:?}", $reader.read_string(size as usize)),
+            Read::VarString => format!("{:?}", $reader.read_var_string()),
+            Read::VarStringAscii => format!("{:?}", $reader.read_var_string_with(Ascii)),
+            Read::CString(max_len) => format!("{:?}", $reader.read_cstring(max_len as usize)),
+            Read::Vec => format!("{:?}", $reader.read_vec(|r| r.read_u16())),
+            Read::Map => format!(
+                "{:?}",
+                $reader.read_map(|r| Ok((r.read_u8()?, r.read_var_bytes()?)))
+            ),
+            Read::Set(unique) => format!("{:?}", $reader.read_set(unique, |r| r.read_varint())),
+        }
+    };
+}
+
+fuzz_target!(|input: &[u8]| {
+    let mut u = Unstructured::new(input);
+    let ops: Vec<Read> = match u.arbitrary() {
+        Ok(ops) => ops,
+        Err(_) => return,
+    };
+
+    let data = u.take_rest();
+    let mut buffer = Buffer::from(data);
+    let mut slice = data;
+    for op in &ops {
+        let expected = reads!(buffer, op);
+        let got = reads!(slice, op);
+        assert_eq!(expected, got, "{:?}", op);
+
+        //A failed read may have consumed part of the slice, so the two can't be compared after.
+        if expected.starts_with("Err") {
+            break;
+        }
+        assert_eq!(buffer.remaining(), slice.len());
+    }
+});
@@ -0,0 +1,153 @@
+#![no_main]
+
+//Runs a sequence of reads over arbitrary bytes. Any read may fail, but none may panic.
+use arbitrary::{Arbitrary, Unstructured};
+use extended_primitives::buffer::{Ascii, HandshakeName};
+use extended_primitives::{BufRead, Buffer};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Read {
+    U8,
+    U16,
+    U16Be,
+    U24,
+    U24Be,
+    U32,
+    U32Be,
+    U48,
+    U48Be,
+    U64,
+    U64Be,
+    U128,
+    U128Be,
+    I24,
+    I24Be,
+    I48,
+    I48Be,
+    I128,
+    I128Be,
+    U256,
+    U256Be,
+    F32,
+    F32Be,
+    F64,
+    F64Be,
+    Bool,
+    VarInt,
+    VarInt2,
+    Uleb128,
+    Uleb128U128,
+    Sleb128,
+    Sleb128I128,
+    Zigzag,
+    ZigzagI128,
+    Hash,
+    Bytes(usize),
+    VarBytes,
+    String(usize),
+    VarString,
+    VarStringAscii,
+    VarStringName,
+    CString(usize),
+    SubBuffer(usize),
+    VarSubBuffer,
+    Vec,
+    Map,
+    Set(bool),
+    Checksum(usize),
+    Seek(usize),
+    SeekBack(usize),
+    SetOffset(usize),
+    Peek,
+    Checkpoint(bool),
+    ExpectConsumed,
+}
+
+fn read(buffer: &mut Buffer, op: &Read) {
+    let _ = match *op {
+        Read::U8 => buffer.read_u8().map(drop),
+        Read::U16 => buffer.read_u16().map(drop),
+        Read::U16Be => buffer.read_u16_be().map(drop),
+        Read::U24 => buffer.read_u24().map(drop),
+        Read::U24Be => buffer.read_u24_be().map(drop),
+        Read::U32 => buffer.read_u32().map(drop),
+        Read::U32Be => buffer.read_u32_be().map(drop),
+        Read::U48 => buffer.read_u48().map(drop),
+        Read::U48Be => buffer.read_u48_be().map(drop),
+        Read::U64 => buffer.read_u64().map(drop),
+        Read::U64Be => buffer.read_u64_be().map(drop),
+        Read::U128 => buffer.read_u128().map(drop),
+        Read::U128Be => buffer.read_u128_be().map(drop),
+        Read::I24 => buffer.read_i24().map(drop),
+        Read::I24Be => buffer.read_i24_be().map(drop),
+        Read::I48 => buffer.read_i48().map(drop),
+        Read::I48Be => buffer.read_i48_be().map(drop),
+        Read::I128 => buffer.read_i128().map(drop),
+        Read::I128Be => buffer.read_i128_be().map(drop),
+        Read::U256 => buffer.read_u256().map(drop),
+        Read::U256Be => buffer.read_u256_be().map(drop),
+        Read::F32 => buffer.read_f32().map(drop),
+        Read::F32Be => buffer.read_f32_be().map(drop),
+        Read::F64 => buffer.read_f64().map(drop),
+        Read::F64Be => buffer.read_f64_be().map(drop),
+        Read::Bool => buffer.read_bool().map(drop),
+        Read::VarInt => buffer.read_varint().map(drop),
+        Read::VarInt2 => buffer.read_varint2().map(drop),
+        Read::Uleb128 => buffer.read_uleb128().map(drop),
+        Read::Uleb128U128 => buffer.read_uleb128_u128().map(drop),
+        Read::Sleb128 => buffer.read_sleb128().map(drop),
+        Read::Sleb128I128 => buffer.read_sleb128_i128().map(drop),
+        Read::Zigzag => buffer.read_zigzag().map(drop),
+        Read::ZigzagI128 => buffer.read_zigzag_i128().map(drop),
+        Read::Hash => buffer.read_hash().map(drop),
+        Read::Bytes(size) => buffer.read_bytes(size).map(drop),
+        Read::VarBytes => buffer.read_var_bytes().map(drop),
+        Read::String(size) => buffer.read_string(size).map(drop),
+        Read::VarString => buffer.read_var_string().map(drop),
+        Read::VarStringAscii => buffer.read_var_string_with(Ascii).map(drop),
+        Read::VarStringName => buffer.read_var_string_with(HandshakeName).map(drop),
+        Read::CString(max_len) => buffer.read_cstring(max_len).map(drop),
+        Read::SubBuffer(len) => buffer
+            .read_sub_buffer(len)
+            .and_then(|mut sub| sub.read_var_bytes())
+            .map(drop),
+        Read::VarSubBuffer => buffer
+            .read_var_sub_buffer()
+            .and_then(|sub| sub.expect_consumed())
+            .map(drop),
+        Read::Vec => buffer.read_vec(|b| b.read_u16()).map(drop),
+        Read::Map => buffer
+            .read_map(|b| Ok((b.read_u8()?, b.read_var_bytes()?)))
+            .map(drop),
+        Read::Set(unique) => buffer.read_set(unique, |b| b.read_varint()).map(drop),
+        Read::Checksum(start) => buffer.read_checksum(start.min(buffer.offset())..).map(drop),
+        Read::Seek(off) => buffer.seek(off),
+        Read::SeekBack(off) => buffer.seek_back(off),
+        Read::SetOffset(offset) => buffer.set_offset(offset),
+        Read::Peek => buffer.peek_u64().map(drop),
+        Read::Checkpoint(commit) => {
+            let mut attempt = buffer.checkpoint();
+            let ret = attempt.read_u32().map(drop);
+            if commit {
+                attempt.commit();
+            }
+            ret
+        }
+        Read::ExpectConsumed => buffer.expect_consumed(),
+    };
+}
+
+fuzz_target!(|input: &[u8]| {
+    let mut u = Unstructured::new(input);
+    let ops: Vec<Read> = match u.arbitrary() {
+        Ok(ops) => ops,
+        Err(_) => return,
+    };
+
+    let mut buffer = Buffer::from(u.take_rest());
+    for op in &ops {
+        read(&mut buffer, op);
+        assert!(buffer.offset() <= buffer.len());
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+//Hex encoding of Hash and Uint256 round-trips, and parsing arbitrary strings never panics.
+use arbitrary::Arbitrary;
+use encodings::{FromHex, ToHex};
+use extended_primitives::{Hash, Uint256};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    hash: Hash,
+    num: Uint256,
+    text: &'a str,
+}
+
+fuzz_target!(|input: Input| {
+    assert_eq!(Hash::from_hex(input.hash.to_hex()).unwrap(), input.hash);
+    assert_eq!(Uint256::from_hex(input.num.to_hex()).unwrap(), input.num);
+    assert_eq!(
+        Uint256::from_dec_str(&input.num.to_dec_string()).unwrap(),
+        input.num
+    );
+
+    if let Ok(hash) = Hash::from_hex(input.text) {
+        assert_eq!(hash.to_hex(), input.text.to_lowercase());
+    }
+    if let Ok(num) = Uint256::from_hex(input.text) {
+        assert_eq!(Uint256::from_hex(num.to_hex()).unwrap(), num);
+    }
+    if let Ok(num) = Uint256::from_dec_str(input.text) {
+        assert_eq!(Uint256::from_dec_str(&num.to_dec_string()).unwrap(), num);
+    }
+});
@@ -0,0 +1,51 @@
+#![no_main]
+
+//Decoding arbitrary text never panics, and whatever decodes encodes back to something that decodes
+//to the same bytes.
+use extended_primitives::encoding::{base32, base58, base64, bech32, hex};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(data) = base58::decode(text) {
+        assert_eq!(base58::encode(&data), text);
+    }
+    if let Ok(data) = base58::decode_check(text) {
+        assert_eq!(base58::encode_check(&data), text);
+    }
+
+    if let Ok((hrp, data, variant)) = bech32::decode(text) {
+        assert_eq!(
+            bech32::encode(&hrp, &data, variant).unwrap(),
+            text.to_lowercase()
+        );
+        let _ = bech32::convert_bits(&data, 5, 8, false);
+    }
+    if let Some(hrp) = text.get(..2) {
+        let _ = bech32::decode_segwit(hrp, text);
+        let _ = bech32::decode_address(hrp, text);
+    }
+
+    if let Ok(data) = base64::decode(text) {
+        assert_eq!(base64::decode(&base64::encode(&data)).unwrap(), data);
+    }
+    if let Ok(data) = base64::decode_url(text) {
+        assert_eq!(
+            base64::decode_url(&base64::encode_url(&data)).unwrap(),
+            data
+        );
+    }
+
+    if let Ok(data) = base32::decode(text) {
+        assert_eq!(base32::decode(&base32::encode(&data)).unwrap(), data);
+    }
+    if let Ok(data) = base32::decode_zbase32(text) {
+        assert_eq!(
+            base32::decode_zbase32(&base32::encode_zbase32(&data)).unwrap(),
+            data
+        );
+    }
+
+    if let Ok(data) = hex::decode(text) {
+        assert_eq!(hex::encode(&data), text.to_lowercase());
+    }
+});
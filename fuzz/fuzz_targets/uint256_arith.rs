@@ -0,0 +1,54 @@
+#![no_main]
+
+//Checks Uint256 arithmetic, which wraps at 2^256, against num-bigint.
+use arbitrary::Arbitrary;
+use extended_primitives::Uint256;
+use libfuzzer_sys::fuzz_target;
+use num_bigint::BigUint;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Xor,
+    Shl(u8),
+    Shr(u8),
+}
+
+fn to_big(num: Uint256) -> BigUint {
+    BigUint::from_bytes_le(&num.to_le_bytes())
+}
+
+fn from_big(num: &BigUint) -> Uint256 {
+    let bytes = (num % (BigUint::from(1u8) << 256u32)).to_bytes_le();
+    Uint256::try_from_le_slice(&bytes).unwrap()
+}
+
+fuzz_target!(|input: (Uint256, Uint256, Op)| {
+    let (a, b, op) = input;
+    let modulus = BigUint::from(1u8) << 256u32;
+    let (big_a, big_b) = (to_big(a), to_big(b));
+
+    let (got, expected) = match op {
+        Op::Add => (a + b, from_big(&(big_a + big_b))),
+        Op::Sub => (a - b, from_big(&(big_a + &modulus - big_b))),
+        Op::Mul => (a * b, from_big(&(big_a * big_b))),
+        Op::Div => {
+            if b == Uint256::default() {
+                return;
+            }
+            (a / b, from_big(&(big_a / big_b)))
+        }
+        Op::And => (a & b, from_big(&(big_a & big_b))),
+        Op::Or => (a | b, from_big(&(big_a | big_b))),
+        Op::Xor => (a ^ b, from_big(&(big_a ^ big_b))),
+        Op::Shl(shift) => (a << shift as usize, from_big(&(big_a << shift as usize))),
+        Op::Shr(shift) => (a >> shift as usize, from_big(&(big_a >> shift as usize))),
+    };
+
+    assert_eq!(got, expected, "{:?} {:?} {:?}", a, op, b);
+});
@@ -0,0 +1,513 @@
+use crate::buf::{BufRead, BufWrite};
+use crate::buffer::{BufferError, Result, StringValidator};
+use crate::encoding::hex;
+use crate::{Hash, Uint256, VarInt};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops;
+
+//A Buffer backed by a [u8; N] instead of a Vec, for encoding without allocating, e.g. an 80 byte
+//block header before hashing it. The write_* and read_* methods come from BufWrite and BufRead.
+//Writes that don't fit error with CapacityExceeded and write nothing. Reads, peeks and seeks work
+//the same as Buffer's. There's no checkpoint, as the buffer is Copy: keeping a copy of it, or of
+//its offset for set_offset, does the same.
+#[derive(Clone, Copy)]
+pub struct ArrayBuffer<const N: usize> {
+    data: [u8; N],
+    len: usize,
+    offset: usize,
+}
+
+impl<const N: usize> ArrayBuffer<N> {
+    pub fn new() -> Self {
+        ArrayBuffer {
+            data: [0; N],
+            len: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    //Drops everything written, and resets the offset.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.offset = 0;
+    }
+
+    fn check_capacity(&self, size: usize) -> Result<()> {
+        if size > N - self.len {
+            return Err(BufferError::CapacityExceeded);
+        }
+        Ok(())
+    }
+
+    pub fn check(&self, size: usize) -> Result<()> {
+        if size > self.remaining() {
+            return Err(BufferError::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    //Borrows the bytes instead of copying them into a Vec like BufRead::read_bytes.
+    pub fn read_bytes_ref(&mut self, size: usize) -> Result<&[u8]> {
+        self.check(size)?;
+
+        let range = self.offset..self.offset + size;
+        self.offset += size;
+
+        Ok(&self.data[range])
+    }
+
+    pub fn read_var_bytes_ref(&mut self) -> Result<&[u8]> {
+        let length = self.read_varint()?;
+        let size = usize::try_from(length).map_err(|_| BufferError::OutOfBounds)?;
+
+        self.read_bytes_ref(size)
+    }
+
+    pub fn seek(&mut self, off: usize) -> Result<()> {
+        self.check(off)?;
+
+        self.offset += off;
+
+        Ok(())
+    }
+
+    pub fn seek_back(&mut self, off: usize) -> Result<()> {
+        if off > self.offset {
+            return Err(BufferError::OutOfBounds);
+        }
+
+        self.offset -= off;
+
+        Ok(())
+    }
+
+    pub fn set_offset(&mut self, offset: usize) -> Result<()> {
+        if offset > self.len {
+            return Err(BufferError::OutOfBounds);
+        }
+
+        self.offset = offset;
+
+        Ok(())
+    }
+
+    pub fn rewind(&mut self) {
+        self.offset = 0;
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.len - self.offset
+    }
+
+    pub fn expect_consumed(&self) -> Result<()> {
+        match self.remaining() {
+            0 => Ok(()),
+            left => Err(BufferError::TrailingBytes(left)),
+        }
+    }
+
+    peek!(peek_u8, read_u8, u8);
+    peek!(peek_u16, read_u16, u16);
+    peek!(peek_u16_be, read_u16_be, u16);
+    peek!(peek_u32, read_u32, u32);
+    peek!(peek_u32_be, read_u32_be, u32);
+    peek!(peek_u64, read_u64, u64);
+    peek!(peek_u64_be, read_u64_be, u64);
+    peek!(peek_u256, read_u256, Uint256);
+    peek!(peek_u256_be, read_u256_be, Uint256);
+    peek!(peek_varint, read_varint, VarInt);
+    peek!(peek_hash, read_hash, Hash);
+
+    pub fn peek_bytes(&self, size: usize) -> Result<&[u8]> {
+        self.check(size)?;
+
+        Ok(&self.data[self.offset..self.offset + size])
+    }
+
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    //Drops whatever f wrote if it errors, for the writes whose size isn't known up front.
+    fn write_or_rollback<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let len = self.len;
+
+        let result = f(self);
+        if result.is_err() {
+            self.len = len;
+            self.offset = self.offset.min(len);
+        }
+
+        result
+    }
+}
+
+//The writes made of several parts check the capacity up front, or roll back when they can't, so
+//they can't be left half written.
+impl<const N: usize> BufWrite for ArrayBuffer<N> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.check_capacity(bytes.len())?;
+
+        self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+
+        Ok(())
+    }
+
+    fn write_var_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let (prefix, len) = VarInt::from(bytes.len()).encode();
+        self.check_capacity(len.saturating_add(bytes.len()))?;
+
+        self.write_bytes(&prefix[..len])?;
+        self.write_bytes(bytes)
+    }
+
+    fn write_cstring(&mut self, string: &str) -> Result<()> {
+        if string.as_bytes().contains(&0) {
+            return Err(BufferError::StringRejected);
+        }
+        self.check_capacity(string.len().saturating_add(1))?;
+
+        self.write_str(string)?;
+        self.write_u8(0)
+    }
+
+    fn fill(&mut self, value: u8, amount: usize) -> Result<()> {
+        self.check_capacity(amount)?;
+
+        self.data[self.len..self.len + amount].fill(value);
+        self.len += amount;
+
+        Ok(())
+    }
+
+    fn write_vec<T, F>(&mut self, items: &[T], mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, &T) -> Result<()>,
+    {
+        self.write_or_rollback(|this| {
+            this.write_varint(items.len())?;
+            for item in items {
+                f(this, item)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn write_map<K, V, F>(&mut self, map: &BTreeMap<K, V>, mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, &K, &V) -> Result<()>,
+    {
+        self.write_or_rollback(|this| {
+            this.write_varint(map.len())?;
+            for (key, value) in map {
+                f(this, key, value)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn write_set<T, F>(&mut self, set: &BTreeSet<T>, mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, &T) -> Result<()>,
+    {
+        self.write_or_rollback(|this| {
+            this.write_varint(set.len())?;
+            for item in set {
+                f(this, item)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl<const N: usize> BufRead for ArrayBuffer<N> {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.check(buf.len())?;
+
+        buf.copy_from_slice(&self.data[self.offset..self.offset + buf.len()]);
+        self.offset += buf.len();
+
+        Ok(())
+    }
+
+    fn remaining_hint(&self) -> Option<usize> {
+        Some(self.remaining())
+    }
+
+    //Decoded in place, so a bad varint doesn't move the offset.
+    fn read_varint(&mut self) -> Result<VarInt> {
+        let (varint, size) = VarInt::decode(&self.data[self.offset..self.len])?;
+
+        self.offset += size;
+
+        Ok(varint)
+    }
+
+    fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String> {
+        let offset = self.offset;
+
+        let string = self.read_var_string()?;
+        if !validator.validate(&string) {
+            self.offset = offset;
+            return Err(BufferError::StringRejected);
+        }
+
+        Ok(string)
+    }
+}
+
+impl<const N: usize> Default for ArrayBuffer<N> {
+    fn default() -> Self {
+        ArrayBuffer::new()
+    }
+}
+
+//A full buffer, ready to be read.
+impl<const N: usize> From<[u8; N]> for ArrayBuffer<N> {
+    fn from(data: [u8; N]) -> Self {
+        ArrayBuffer {
+            data,
+            len: N,
+            offset: 0,
+        }
+    }
+}
+
+//Only the bytes written so far take part, not the unused capacity.
+impl<const N: usize> PartialEq for ArrayBuffer<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset && self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl<const N: usize> Eq for ArrayBuffer<N> {}
+
+impl<const N: usize> ops::Deref for ArrayBuffer<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.to_bytes()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for ArrayBuffer<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.to_bytes()
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Offset: {}, Buffer: ", self.offset)?;
+        hex::write(f, self.to_bytes())
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{HashEngine, Sha256d};
+    use crate::{Buffer, CoreVarInt, Hash, Uint256};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_matches_buffer() {
+        let mut array = ArrayBuffer::<128>::new();
+        let mut buffer = Buffer::new();
+
+        array.write_u32(1).unwrap();
+        buffer.write_u32(1);
+        array.write_u64_be(0x0102030405060708).unwrap();
+        buffer.write_u64_be(0x0102030405060708);
+        array.write_i24(-2).unwrap();
+        buffer.write_i24(-2).unwrap();
+        array.write_f64(f64::NAN).unwrap();
+        buffer.write_f64(f64::NAN);
+        array.write_varint(0xFFFF).unwrap();
+        buffer.write_varint(0xFFFF);
+        array.write_varint2(0x4000).unwrap();
+        buffer.write_varint2(0x4000);
+        array.write_var_string("hns").unwrap();
+        buffer.write_var_string("hns");
+        array.write_u256(Uint256::from_u64_const(7)).unwrap();
+        buffer.write_u256(Uint256::from_u64_const(7));
+
+        assert_eq!(array.to_bytes(), buffer.to_bytes());
+
+        assert_eq!(array.read_u32().unwrap(), 1);
+        assert_eq!(array.read_u64_be().unwrap(), 0x0102030405060708);
+        assert_eq!(array.read_i24().unwrap(), -2);
+        assert!(array.read_f64().unwrap().is_nan());
+        assert_eq!(array.read_varint().unwrap(), VarInt::from(0xFFFFu64));
+        assert_eq!(array.read_varint2().unwrap(), CoreVarInt::from(0x4000u64));
+        assert_eq!(array.read_var_string().unwrap(), "hns");
+        assert_eq!(array.read_u256().unwrap(), Uint256::from_u64_const(7));
+        assert!(array.expect_consumed().is_ok());
+        assert!(matches!(array.read_u8(), Err(BufferError::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        let mut array = ArrayBuffer::<4>::new();
+        array.write_u16(1).unwrap();
+
+        assert!(matches!(
+            array.write_u32(1),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert!(matches!(
+            array.write_var_bytes(&[1, 2]),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert!(matches!(
+            array.write_cstring("ab"),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert_eq!(array.len(), 2);
+
+        array.write_var_bytes(&[9]).unwrap();
+        assert_eq!(array.to_bytes(), &[1, 0, 1, 9]);
+        array.seek(2).unwrap();
+        assert_eq!(array.read_var_bytes_ref().unwrap(), &[9]);
+        assert!(matches!(
+            array.write_u8(0),
+            Err(BufferError::CapacityExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_collection_rollback() {
+        let mut array = ArrayBuffer::<6>::new();
+        array.write_u8(0xFF).unwrap();
+
+        //The count and the first two items fit, the third doesn't.
+        assert!(matches!(
+            array.write_vec(&[1u16, 2, 3], |a, item| a.write_u16(*item)),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert_eq!(array.to_bytes(), &[0xFF]);
+
+        let set: BTreeSet<u32> = [1, 2].iter().cloned().collect();
+        assert!(matches!(
+            array.write_set(&set, |a, item| a.write_u32(*item)),
+            Err(BufferError::CapacityExceeded)
+        ));
+        let map: BTreeMap<u8, u32> = [(1, 1), (2, 2)].iter().cloned().collect();
+        assert!(matches!(
+            array.write_map(&map, |a, key, value| {
+                a.write_u8(*key)?;
+                a.write_u32(*value)
+            }),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert_eq!(array.to_bytes(), &[0xFF]);
+
+        array
+            .write_vec(&[1u16, 2], |a, item| a.write_u16(*item))
+            .unwrap();
+        assert_eq!(array.to_bytes(), &[0xFF, 2, 1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_peek_and_seek() {
+        let mut array = ArrayBuffer::<8>::new();
+        array.write_u16_be(0x0102).unwrap();
+        array.write_varint(0xFD).unwrap();
+
+        assert_eq!(array.peek_u8().unwrap(), 1);
+        assert_eq!(array.peek_u16_be().unwrap(), 0x0102);
+        assert_eq!(array.peek_bytes(2).unwrap(), &[1, 2]);
+        //Only the bytes written can be peeked, not the unused capacity.
+        assert!(array.peek_u64().is_err());
+        assert_eq!(array.offset(), 0);
+
+        array.seek(2).unwrap();
+        assert_eq!(array.peek_varint().unwrap(), VarInt::from(0xFDu64));
+        array.seek_back(1).unwrap();
+        assert_eq!(array.read_u8().unwrap(), 2);
+        assert!(array.seek_back(2).is_ok());
+        assert!(array.seek_back(1).is_err());
+
+        array.set_offset(5).unwrap();
+        assert!(array.expect_consumed().is_ok());
+        assert!(array.set_offset(6).is_err());
+    }
+
+    #[test]
+    fn test_header_hash() {
+        //The Bitcoin genesis block header.
+        let mut header = ArrayBuffer::<80>::new();
+        header.write_u32(1).unwrap();
+        header.write_hash(Hash::default()).unwrap();
+        header
+            .write_hash(hash!(
+                "3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a"
+            ))
+            .unwrap();
+        header.write_u32(1231006505).unwrap();
+        header.write_u32(0x1d00ffff).unwrap();
+        header.write_u32(2083236893).unwrap();
+        assert_eq!(header.len(), header.capacity());
+
+        let mut engine = Sha256d::default();
+        engine.input(&header);
+        assert_eq!(
+            engine.finalize().reversed(),
+            hash!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+        );
+    }
+
+    #[test]
+    fn test_from_array() {
+        let mut array = ArrayBuffer::from([0xFD, 0x03, 0x00, 0xAA]);
+        assert!(matches!(
+            array.read_varint(),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+
+        array.seek(3).unwrap();
+        assert_eq!(array.read_bytes_ref(1).unwrap(), &[0xAA]);
+        array.rewind();
+        array.seek(3).unwrap();
+        assert_eq!(array.read_bytes(1).unwrap(), vec![0xAA]);
+        assert_eq!(array.to_string(), "Offset: 4, Buffer: fd0300aa");
+    }
+}
@@ -0,0 +1,1102 @@
+use crate::buffer::{
+    canonical_f32, canonical_f64, check_f32, check_f64, BufferError, Result, StringValidator,
+};
+use crate::hash::{HashEngine, Sha256, Sha256d};
+use crate::{Buffer, CoreVarInt, Hash, Uint256, VarInt};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+
+//The write side of Buffer's API, for encoders that are generic over where the bytes go, e.g.
+//
+//    fn encode<W: BufWrite>(&self, writer: &mut W) -> Result<()>
+//
+//can write into a Buffer, a Vec, a stack ArrayBuffer, or straight into a Sha256 or Sha256d. Only
+//write_bytes has to be implemented. A writer that runs out of space errors with
+//CapacityExceeded, which can leave part of a value written.
+pub trait BufWrite {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()>;
+
+    fn write_u8(&mut self, data: u8) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u16(&mut self, data: u16) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, data: u32) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, data: u64) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, data: u128) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u256(&mut self, data: Uint256) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_u8_be(&mut self, data: u8) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_u16_be(&mut self, data: u16) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_u32_be(&mut self, data: u32) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_u64_be(&mut self, data: u64) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_u128_be(&mut self, data: u128) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_u256_be(&mut self, data: Uint256) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_i8(&mut self, data: i8) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_i16(&mut self, data: i16) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, data: i32) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, data: i64) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_i128(&mut self, data: i128) -> Result<()> {
+        self.write_bytes(&data.to_le_bytes())
+    }
+
+    fn write_i8_be(&mut self, data: i8) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_i16_be(&mut self, data: i16) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_i32_be(&mut self, data: i32) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_i64_be(&mut self, data: i64) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    fn write_i128_be(&mut self, data: i128) -> Result<()> {
+        self.write_bytes(&data.to_be_bytes())
+    }
+
+    //Every NaN is written as the canonical quiet NaN, the same as Buffer.
+    fn write_f32(&mut self, data: f32) -> Result<()> {
+        self.write_u32(canonical_f32(data).to_bits())
+    }
+
+    fn write_f32_be(&mut self, data: f32) -> Result<()> {
+        self.write_u32_be(canonical_f32(data).to_bits())
+    }
+
+    fn write_f64(&mut self, data: f64) -> Result<()> {
+        self.write_u64(canonical_f64(data).to_bits())
+    }
+
+    fn write_f64_be(&mut self, data: f64) -> Result<()> {
+        self.write_u64_be(canonical_f64(data).to_bits())
+    }
+
+    fn write_bool(&mut self, data: bool) -> Result<()> {
+        self.write_u8(data as u8)
+    }
+
+    //Error with OutOfRange if the value doesn't fit.
+    fn write_u24(&mut self, data: u32) -> Result<()> {
+        let (bytes, len) = encode_uint(data as u64, 3, false)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_u24_be(&mut self, data: u32) -> Result<()> {
+        let (bytes, len) = encode_uint(data as u64, 3, true)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_u48(&mut self, data: u64) -> Result<()> {
+        let (bytes, len) = encode_uint(data, 6, false)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_u48_be(&mut self, data: u64) -> Result<()> {
+        let (bytes, len) = encode_uint(data, 6, true)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_i24(&mut self, data: i32) -> Result<()> {
+        let (bytes, len) = encode_int(data as i64, 3, false)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_i24_be(&mut self, data: i32) -> Result<()> {
+        let (bytes, len) = encode_int(data as i64, 3, true)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_i48(&mut self, data: i64) -> Result<()> {
+        let (bytes, len) = encode_int(data, 6, false)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_i48_be(&mut self, data: i64) -> Result<()> {
+        let (bytes, len) = encode_int(data, 6, true)?;
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_hash(&mut self, hash: Hash) -> Result<()> {
+        self.write_bytes(&hash.to_array())
+    }
+
+    fn write_varint(&mut self, data: usize) -> Result<()> {
+        let (bytes, len) = VarInt::from(data).encode();
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_varint2(&mut self, data: u64) -> Result<()> {
+        let (bytes, len) = CoreVarInt::from(data).encode();
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_uleb128(&mut self, data: u64) -> Result<()> {
+        let (bytes, len) = encode_uleb(data as u128);
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_uleb128_u128(&mut self, data: u128) -> Result<()> {
+        let (bytes, len) = encode_uleb(data);
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_sleb128(&mut self, data: i64) -> Result<()> {
+        let (bytes, len) = encode_sleb(data as i128);
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_sleb128_i128(&mut self, data: i128) -> Result<()> {
+        let (bytes, len) = encode_sleb(data);
+        self.write_bytes(&bytes[..len])
+    }
+
+    fn write_zigzag(&mut self, data: i64) -> Result<()> {
+        self.write_uleb128(((data << 1) ^ (data >> 63)) as u64)
+    }
+
+    fn write_zigzag_i128(&mut self, data: i128) -> Result<()> {
+        self.write_uleb128_u128(((data << 1) ^ (data >> 127)) as u128)
+    }
+
+    fn write_var_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_varint(bytes.len())?;
+        self.write_bytes(bytes)
+    }
+
+    fn write_str(&mut self, string: &str) -> Result<()> {
+        self.write_bytes(string.as_bytes())
+    }
+
+    fn write_string(&mut self, string: String) -> Result<()> {
+        self.write_str(&string)
+    }
+
+    fn write_var_string(&mut self, string: &str) -> Result<()> {
+        self.write_var_bytes(string.as_bytes())
+    }
+
+    fn write_var_string_with<V: StringValidator>(
+        &mut self,
+        string: &str,
+        validator: V,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        if !validator.validate(string) {
+            return Err(BufferError::StringRejected);
+        }
+
+        self.write_var_string(string)
+    }
+
+    fn write_cstring(&mut self, string: &str) -> Result<()> {
+        if string.as_bytes().contains(&0) {
+            return Err(BufferError::StringRejected);
+        }
+
+        self.write_str(string)?;
+        self.write_u8(0)
+    }
+
+    fn fill(&mut self, value: u8, amount: usize) -> Result<()> {
+        let chunk = [value; 64];
+        let mut left = amount;
+
+        while left > 0 {
+            let size = left.min(chunk.len());
+            self.write_bytes(&chunk[..size])?;
+            left -= size;
+        }
+
+        Ok(())
+    }
+
+    fn write_vec<T, F>(&mut self, items: &[T], mut f: F) -> Result<()>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self, &T) -> Result<()>,
+    {
+        self.write_varint(items.len())?;
+        for item in items {
+            f(self, item)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_map<K, V, F>(&mut self, map: &BTreeMap<K, V>, mut f: F) -> Result<()>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self, &K, &V) -> Result<()>,
+    {
+        self.write_varint(map.len())?;
+        for (key, value) in map {
+            f(self, key, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_set<T, F>(&mut self, set: &BTreeSet<T>, mut f: F) -> Result<()>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self, &T) -> Result<()>,
+    {
+        self.write_varint(set.len())?;
+        for item in set {
+            f(self, item)?;
+        }
+
+        Ok(())
+    }
+}
+
+//The read side of Buffer's API. Only read_into has to be implemented, which should error with
+//OutOfBounds if it can't fill the whole slice. A read that fails part way may have consumed the
+//bytes before the failure.
+pub trait BufRead {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()>;
+
+    //How many bytes are left, for readers that know. read_vec caps its preallocation by it, so a
+    //huge count can't allocate more than the bytes left could hold. The count itself is only
+    //checked by the reads of the items, as an item can take no bytes at all.
+    fn remaining_hint(&self) -> Option<usize> {
+        None
+    }
+
+    //Errors with TrailingBytes if the reader knows it has bytes left, e.g. after decoding a sub
+    //buffer. Readers that don't know their length always pass.
+    fn expect_consumed(&self) -> Result<()> {
+        match self.remaining_hint() {
+            Some(left) if left > 0 => Err(BufferError::TrailingBytes(left)),
+            _ => Ok(()),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0; 1];
+        self.read_into(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_into(&mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_into(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_into(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_u128(&mut self) -> Result<u128> {
+        let mut buf = [0; 16];
+        self.read_into(&mut buf)?;
+        Ok(u128::from_le_bytes(buf))
+    }
+
+    fn read_u256(&mut self) -> Result<Uint256> {
+        let mut buf = [0; 32];
+        self.read_into(&mut buf)?;
+        Ok(Uint256::from_le_bytes(buf))
+    }
+
+    fn read_u16_be(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_into(&mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn read_u32_be(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_into(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn read_u64_be(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_into(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn read_u128_be(&mut self) -> Result<u128> {
+        let mut buf = [0; 16];
+        self.read_into(&mut buf)?;
+        Ok(u128::from_be_bytes(buf))
+    }
+
+    fn read_u256_be(&mut self) -> Result<Uint256> {
+        let mut buf = [0; 32];
+        self.read_into(&mut buf)?;
+        Ok(Uint256::from_be_bytes(buf))
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    fn read_i16(&mut self) -> Result<i16> {
+        Ok(self.read_u16()? as i16)
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        Ok(self.read_u64()? as i64)
+    }
+
+    fn read_i128(&mut self) -> Result<i128> {
+        Ok(self.read_u128()? as i128)
+    }
+
+    fn read_i16_be(&mut self) -> Result<i16> {
+        Ok(self.read_u16_be()? as i16)
+    }
+
+    fn read_i32_be(&mut self) -> Result<i32> {
+        Ok(self.read_u32_be()? as i32)
+    }
+
+    fn read_i64_be(&mut self) -> Result<i64> {
+        Ok(self.read_u64_be()? as i64)
+    }
+
+    fn read_i128_be(&mut self) -> Result<i128> {
+        Ok(self.read_u128_be()? as i128)
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        check_f32(self.read_u32()?)
+    }
+
+    fn read_f32_be(&mut self) -> Result<f32> {
+        check_f32(self.read_u32_be()?)
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        check_f64(self.read_u64()?)
+    }
+
+    fn read_f64_be(&mut self) -> Result<f64> {
+        check_f64(self.read_u64_be()?)
+    }
+
+    fn read_bool(&mut self) -> Result<bool> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(BufferError::InvalidBool(byte)),
+        }
+    }
+
+    fn read_u24(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_into(&mut buf[..3])?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u24_be(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_into(&mut buf[1..])?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn read_u48(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_into(&mut buf[..6])?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_u48_be(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_into(&mut buf[2..])?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    //Sign extended from the top bit that was read.
+    fn read_i24(&mut self) -> Result<i32> {
+        Ok(((self.read_u24()? << 8) as i32) >> 8)
+    }
+
+    fn read_i24_be(&mut self) -> Result<i32> {
+        Ok(((self.read_u24_be()? << 8) as i32) >> 8)
+    }
+
+    fn read_i48(&mut self) -> Result<i64> {
+        Ok(((self.read_u48()? << 16) as i64) >> 16)
+    }
+
+    fn read_i48_be(&mut self) -> Result<i64> {
+        Ok(((self.read_u48_be()? << 16) as i64) >> 16)
+    }
+
+    fn read_hash(&mut self) -> Result<Hash> {
+        let mut array = [0; 32];
+        self.read_into(&mut array)?;
+        Ok(Hash::from(array))
+    }
+
+    fn read_varint(&mut self) -> Result<VarInt> {
+        let mut buf = [0; 9];
+        self.read_into(&mut buf[..1])?;
+
+        let len = match buf[0] {
+            0xFF => 9,
+            0xFE => 5,
+            0xFD => 3,
+            _ => 1,
+        };
+        self.read_into(&mut buf[1..len])?;
+
+        Ok(VarInt::decode(&buf[..len])?.0)
+    }
+
+    fn read_varint2(&mut self) -> Result<CoreVarInt> {
+        Ok(CoreVarInt::decode_with(|| self.read_u8())?.0)
+    }
+
+    fn read_uleb128(&mut self) -> Result<u64> {
+        Ok(read_uleb(self, 64)? as u64)
+    }
+
+    fn read_uleb128_u128(&mut self) -> Result<u128> {
+        read_uleb(self, 128)
+    }
+
+    fn read_sleb128(&mut self) -> Result<i64> {
+        Ok(read_sleb(self, 64)? as i64)
+    }
+
+    fn read_sleb128_i128(&mut self) -> Result<i128> {
+        read_sleb(self, 128)
+    }
+
+    fn read_zigzag(&mut self) -> Result<i64> {
+        let num = self.read_uleb128()?;
+        Ok((num >> 1) as i64 ^ -((num & 1) as i64))
+    }
+
+    fn read_zigzag_i128(&mut self) -> Result<i128> {
+        let num = self.read_uleb128_u128()?;
+        Ok((num >> 1) as i128 ^ -((num & 1) as i128))
+    }
+
+    //The size isn't trusted for allocation, so the bytes are read in chunks and a bogus size
+    //fails with OutOfBounds once the reader runs out.
+    fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+
+        while bytes.len() < size {
+            let start = bytes.len();
+            bytes.resize(start + (size - start).min(4096), 0);
+            self.read_into(&mut bytes[start..])?;
+        }
+
+        Ok(bytes)
+    }
+
+    fn read_var_bytes(&mut self) -> Result<Vec<u8>> {
+        let size = read_count(self)?;
+        self.read_bytes(size)
+    }
+
+    fn read_string(&mut self, size: usize) -> Result<String> {
+        Ok(String::from_utf8(self.read_bytes(size)?)?)
+    }
+
+    fn read_var_string(&mut self) -> Result<String> {
+        let size = read_count(self)?;
+        self.read_string(size)
+    }
+
+    //Like Buffer's, a rejected string is left unread by every reader in this crate. Readers that
+    //can't go back, which is all this default can assume, have still read it.
+    fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String>
+    where
+        Self: Sized,
+    {
+        let string = self.read_var_string()?;
+        if !validator.validate(&string) {
+            return Err(BufferError::StringRejected);
+        }
+
+        Ok(string)
+    }
+
+    fn read_cstring(&mut self, max_len: usize) -> Result<String> {
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.read_u8()? {
+                0 => return Ok(String::from_utf8(bytes)?),
+                _ if bytes.len() == max_len => return Err(BufferError::StringTooLong(max_len)),
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    fn read_vec<T, F>(&mut self, mut f: F) -> Result<Vec<T>>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<T>,
+    {
+        let count = read_count(self)?;
+
+        //Preallocated for no more items than the bytes left could hold, if they're known.
+        let limit = self.remaining_hint().unwrap_or(0) / mem::size_of::<T>().max(1);
+        let mut items = Vec::with_capacity(count.min(limit));
+        for _ in 0..count {
+            items.push(f(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn read_map<K, V, F>(&mut self, mut f: F) -> Result<BTreeMap<K, V>>
+    where
+        Self: Sized,
+        K: Ord,
+        F: FnMut(&mut Self) -> Result<(K, V)>,
+    {
+        let count = read_count(self)?;
+
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            let (key, value) = f(self)?;
+
+            if let Some((last, _)) = map.iter().next_back() {
+                match key.cmp(last) {
+                    core::cmp::Ordering::Equal => return Err(BufferError::DuplicateEntry),
+                    core::cmp::Ordering::Less => return Err(BufferError::UnsortedEntries),
+                    core::cmp::Ordering::Greater => {}
+                }
+            }
+
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
+
+    fn read_set<T, F>(&mut self, unique: bool, mut f: F) -> Result<BTreeSet<T>>
+    where
+        Self: Sized,
+        T: Ord,
+        F: FnMut(&mut Self) -> Result<T>,
+    {
+        let count = read_count(self)?;
+
+        let mut set = BTreeSet::new();
+        for _ in 0..count {
+            if !set.insert(f(self)?) && unique {
+                return Err(BufferError::DuplicateEntry);
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+fn read_count<R: BufRead + ?Sized>(reader: &mut R) -> Result<usize> {
+    let count = reader.read_varint()?;
+    usize::try_from(count).map_err(|_| BufferError::OutOfBounds)
+}
+
+//Returns the value's size bytes in the first `len` bytes of the array, or OutOfRange if it
+//doesn't fit.
+fn encode_uint(data: u64, size: usize, big_endian: bool) -> Result<([u8; 8], usize)> {
+    if data >> (size * 8) != 0 {
+        return Err(BufferError::OutOfRange);
+    }
+
+    let mut bytes = [0; 8];
+    if big_endian {
+        bytes[..size].copy_from_slice(&data.to_be_bytes()[8 - size..]);
+    } else {
+        bytes[..size].copy_from_slice(&data.to_le_bytes()[..size]);
+    }
+
+    Ok((bytes, size))
+}
+
+fn encode_int(data: i64, size: usize, big_endian: bool) -> Result<([u8; 8], usize)> {
+    let bits = size * 8;
+    if data < -(1 << (bits - 1)) || data >= 1 << (bits - 1) {
+        return Err(BufferError::OutOfRange);
+    }
+
+    //Drop the sign extension so encode_uint's range check passes.
+    encode_uint(data as u64 & ((1 << bits) - 1), size, big_endian)
+}
+
+//A u128 takes at most 19 LEB128 bytes.
+pub(crate) fn encode_uleb(data: u128) -> ([u8; 19], usize) {
+    let mut bytes = [0; 19];
+    let mut num = data;
+    let mut len = 0;
+
+    loop {
+        let byte = (num & 0x7F) as u8;
+        num >>= 7;
+
+        if num == 0 {
+            bytes[len] = byte;
+            return (bytes, len + 1);
+        }
+
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+pub(crate) fn encode_sleb(data: i128) -> ([u8; 19], usize) {
+    let mut bytes = [0; 19];
+    let mut num = data;
+    let mut len = 0;
+
+    loop {
+        let byte = (num & 0x7F) as u8;
+        num >>= 7;
+
+        //Done once the rest is only the sign extension of the byte's 7th bit.
+        if (num == 0 && byte & 0x40 == 0) || (num == -1 && byte & 0x40 != 0) {
+            bytes[len] = byte;
+            return (bytes, len + 1);
+        }
+
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+//Reads an unsigned LEB128 that has to fit in `bits`. A trailing zero byte is rejected as
+//non-minimal, the same as read_varint does for CompactSize.
+fn read_uleb<R: BufRead + ?Sized>(reader: &mut R, bits: u32) -> Result<u128> {
+    let mut num: u128 = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = reader.read_u8()?;
+        let low = (byte & 0x7F) as u128;
+
+        if shift + 7 > bits && low >> (bits - shift) != 0 {
+            return Err(BufferError::VarIntOverflow);
+        }
+
+        num |= low << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            if byte == 0 && shift > 7 {
+                return Err(BufferError::NonMinimalVarInt);
+            }
+            return Ok(num);
+        }
+
+        if shift >= bits {
+            return Err(BufferError::VarIntOverflow);
+        }
+    }
+}
+
+//Reads a signed LEB128 that has to fit in `bits`. A last byte that only repeats the sign of
+//the byte before it is rejected as non-minimal.
+fn read_sleb<R: BufRead + ?Sized>(reader: &mut R, bits: u32) -> Result<i128> {
+    let mut num: i128 = 0;
+    let mut shift = 0;
+    let mut prev = None;
+
+    loop {
+        let byte = reader.read_u8()?;
+        let low = (byte & 0x7F) as i128;
+
+        //Whatever doesn't fit must be the sign extension of the last bit that does.
+        if shift + 7 > bits {
+            let sign = low >> (bits - shift - 1);
+            if sign != 0 && sign != 0x7F >> (bits - shift - 1) {
+                return Err(BufferError::VarIntOverflow);
+            }
+        }
+
+        num |= low << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            match (prev, byte) {
+                (Some(prev), 0x00) if prev & 0x40 == 0 => {
+                    return Err(BufferError::NonMinimalVarInt)
+                }
+                (Some(prev), 0x7F) if prev & 0x40 != 0 => {
+                    return Err(BufferError::NonMinimalVarInt)
+                }
+                _ => {}
+            }
+
+            if byte & 0x40 != 0 && shift < 128 {
+                num |= !0 << shift;
+            }
+            return Ok(num);
+        }
+
+        if shift >= bits {
+            return Err(BufferError::VarIntOverflow);
+        }
+        prev = Some(byte);
+    }
+}
+
+impl BufWrite for Buffer {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        Buffer::write_bytes(self, bytes);
+        Ok(())
+    }
+}
+
+impl BufRead for Buffer {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        buf.copy_from_slice(self.peek_bytes(buf.len())?);
+        self.seek(buf.len())
+    }
+
+    fn remaining_hint(&self) -> Option<usize> {
+        Some(self.remaining())
+    }
+
+    fn read_varint(&mut self) -> Result<VarInt> {
+        Buffer::read_varint(self)
+    }
+
+    fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String> {
+        Buffer::read_var_string_with(self, validator)
+    }
+}
+
+impl BufWrite for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+//Writes to the front of the slice and moves it past what was written, like io::Write does.
+impl BufWrite for &mut [u8] {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() > self.len() {
+            return Err(BufferError::CapacityExceeded);
+        }
+
+        let (head, tail) = core::mem::take(self).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        *self = tail;
+
+        Ok(())
+    }
+}
+
+//Reads from the front of the slice and moves it past what was read, like io::Read does.
+impl BufRead for &[u8] {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        if buf.len() > self.len() {
+            return Err(BufferError::OutOfBounds);
+        }
+
+        let (head, tail) = self.split_at(buf.len());
+        buf.copy_from_slice(head);
+        *self = tail;
+
+        Ok(())
+    }
+
+    fn remaining_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String> {
+        let start = *self;
+
+        let string = self.read_var_string()?;
+        if !validator.validate(&string) {
+            *self = start;
+            return Err(BufferError::StringRejected);
+        }
+
+        Ok(string)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BufWrite for std::io::Cursor<T>
+where
+    std::io::Cursor<T>: std::io::Write,
+{
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        std::io::Write::write_all(self, bytes).map_err(|_| BufferError::CapacityExceeded)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: AsRef<[u8]>> BufRead for std::io::Cursor<T> {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        std::io::Read::read_exact(self, buf).map_err(|_| BufferError::OutOfBounds)
+    }
+
+    fn remaining_hint(&self) -> Option<usize> {
+        let len = self.get_ref().as_ref().len() as u64;
+        usize::try_from(len.saturating_sub(self.position())).ok()
+    }
+
+    fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String> {
+        let position = self.position();
+
+        let string = self.read_var_string()?;
+        if !validator.validate(&string) {
+            self.set_position(position);
+            return Err(BufferError::StringRejected);
+        }
+
+        Ok(string)
+    }
+}
+
+//Writing into an engine hashes the encoding without ever holding it in memory. Implemented per
+//engine rather than for every HashEngine, so other types can still implement both traits.
+macro_rules! impl_engine_write {
+    ($($engine:ty),*) => {
+        $(
+            impl BufWrite for $engine {
+                fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+                    self.input(bytes);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_engine_write!(Sha256, Sha256d);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Ascii;
+    use crate::{ArrayBuffer, SizeCounter};
+    use alloc::{format, vec};
+
+    //An encoder written once against the trait.
+    fn encode<W: BufWrite>(writer: &mut W) -> Result<()> {
+        writer.write_u32(1)?;
+        writer.write_i48_be(-2)?;
+        writer.write_f32(f32::NAN)?;
+        writer.write_varint(0x10000)?;
+        writer.write_varint2(0x4000)?;
+        writer.write_sleb128(-129)?;
+        writer.write_var_string("hns")?;
+        writer.write_string(String::from("!"))?;
+        writer.write_cstring("ab")?;
+        writer.write_vec(&[1u16, 2], |writer, item| writer.write_u16_be(*item))?;
+        writer.fill(0xAA, 100)?;
+        writer.write_hash(Hash::from([7; 32]))
+    }
+
+    fn decode<R: BufRead>(reader: &mut R) -> Result<()> {
+        assert_eq!(reader.read_u32()?, 1);
+        assert_eq!(reader.read_i48_be()?, -2);
+        assert!(reader.read_f32()?.is_nan());
+        assert_eq!(reader.read_varint()?, VarInt::from(0x10000u64));
+        assert_eq!(reader.read_varint2()?, CoreVarInt::from(0x4000u64));
+        assert_eq!(reader.read_sleb128()?, -129);
+        assert_eq!(reader.read_var_string()?, "hns");
+        assert_eq!(reader.read_string(1)?, "!");
+        assert_eq!(reader.read_cstring(2)?, "ab");
+        assert_eq!(reader.read_vec(|reader| reader.read_u16_be())?, vec![1, 2]);
+        assert_eq!(reader.read_bytes(100)?, vec![0xAA; 100]);
+        assert_eq!(reader.read_hash()?, Hash::from([7; 32]));
+        assert!(matches!(reader.read_u8(), Err(BufferError::OutOfBounds)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_writers_agree() {
+        let mut buffer = Buffer::new();
+        encode(&mut buffer).unwrap();
+        let expected = buffer.to_vec();
+
+        let mut vec = Vec::new();
+        encode(&mut vec).unwrap();
+        assert_eq!(vec, expected);
+
+        let mut array = ArrayBuffer::<256>::new();
+        encode(&mut array).unwrap();
+        assert_eq!(array.to_bytes(), &expected[..]);
+
+        let mut storage = [0; 256];
+        let mut slice = &mut storage[..];
+        encode(&mut slice).unwrap();
+        let left = slice.len();
+        assert_eq!(&storage[..256 - left], &expected[..]);
+
+        let mut counter = SizeCounter::new();
+        encode(&mut counter).unwrap();
+        assert_eq!(counter.size(), expected.len());
+
+        let mut engine = Sha256d::default();
+        encode(&mut engine).unwrap();
+        let mut direct = Sha256d::default();
+        direct.input(&expected);
+        assert_eq!(engine.finalize(), direct.finalize());
+    }
+
+    #[test]
+    fn test_readers_agree() {
+        let mut buffer = Buffer::new();
+        encode(&mut buffer).unwrap();
+
+        decode(&mut buffer.clone()).unwrap();
+        decode(&mut &buffer[..]).unwrap();
+
+        let mut array = ArrayBuffer::<256>::new();
+        array.write_bytes(&buffer).unwrap();
+        decode(&mut array).unwrap();
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        let mut storage = [0; 3];
+        let mut slice = &mut storage[..];
+        assert!(matches!(
+            BufWrite::write_u32(&mut slice, 1),
+            Err(BufferError::CapacityExceeded)
+        ));
+        assert_eq!(slice.len(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_cursor() {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        encode(&mut cursor).unwrap();
+
+        cursor.set_position(0);
+        decode(&mut cursor).unwrap();
+
+        let mut cursor = std::io::Cursor::new([0; 3]);
+        assert!(matches!(
+            cursor.write_u32(1),
+            Err(BufferError::CapacityExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_varint2_matches_decode() {
+        let cases: &[&[u8]] = &[&[0x00], &[0x80, 0x00], &[0x80], &[0xFF; 10], &[0x8E; 11]];
+        for bytes in cases {
+            let mut reader = *bytes;
+            assert_eq!(
+                format!("{:?}", reader.read_varint2()),
+                format!("{:?}", CoreVarInt::decode(bytes).map(|(varint, _)| varint))
+            );
+        }
+    }
+
+    fn check_rejected<R: BufRead>(reader: &mut R) {
+        assert!(matches!(
+            reader.read_var_string_with(|s: &str| s.is_empty()),
+            Err(BufferError::StringRejected)
+        ));
+        assert_eq!(reader.read_var_string_with(Ascii).unwrap(), "hns");
+    }
+
+    #[test]
+    fn test_rejected_string_unread() {
+        let bytes = [3, b'h', b'n', b's'];
+
+        check_rejected(&mut Buffer::from(bytes.to_vec()));
+        check_rejected(&mut ArrayBuffer::from(bytes));
+        check_rejected(&mut &bytes[..]);
+        #[cfg(feature = "std")]
+        check_rejected(&mut std::io::Cursor::new(bytes));
+    }
+
+    #[test]
+    fn test_bogus_length() {
+        let mut reader = &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00][..];
+        assert!(matches!(
+            reader.read_var_bytes(),
+            Err(BufferError::OutOfBounds)
+        ));
+
+        let mut reader = &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00][..];
+        assert!(matches!(
+            reader.read_vec(|reader| reader.read_u8()),
+            Err(BufferError::OutOfBounds)
+        ));
+
+        //Items can take no bytes, so a count past the bytes left is still valid.
+        let mut array = ArrayBuffer::from([0x03]);
+        assert_eq!(array.read_vec(|_| Ok(())).unwrap(), vec![(), (), ()]);
+    }
+}
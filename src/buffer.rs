@@ -1,25 +1,114 @@
-use crate::{Hash, Uint256, VarInt};
+use crate::buf::{BufRead, BufWrite};
+use crate::encoding::{
+    base32, base58, base64, bech32, hex, Base32Error, Base58Error, Base64Error, Bech32Error,
+    Variant,
+};
+use crate::{CoreVarInt, Hash, Uint256, VarInt};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{FromUtf8Error, String};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops;
+use core::slice::SliceIndex;
+#[cfg(feature = "std")]
 use encodings::{FromHex, FromHexError, ToHex};
-use std::fmt;
-use std::ops;
 
-#[derive(thiserror::Error, Debug)]
+#[derive(Debug)]
 pub enum BufferError {
-    #[error("Read Out of Bounds")]
     OutOfBounds,
-    #[error(transparent)]
-    InvalidString(#[from] std::string::FromUtf8Error),
-    #[error("Non-Minimal VarInt")]
+    InvalidString(FromUtf8Error),
     NonMinimalVarInt,
+    VarIntOverflow,
+    TrailingBytes(usize),
+    OutOfRange,
+    InvalidChecksum,
+    NonCanonicalNaN,
+    InvalidBool(u8),
+    StringRejected,
+    StringTooLong(usize),
+    DuplicateEntry,
+    UnsortedEntries,
+    CapacityExceeded,
+    StalePlaceholder,
 }
 
-pub type Result<T> = std::result::Result<T, BufferError>;
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BufferError::OutOfBounds => write!(f, "Read Out of Bounds"),
+            BufferError::InvalidString(e) => fmt::Display::fmt(e, f),
+            BufferError::NonMinimalVarInt => write!(f, "Non-Minimal VarInt"),
+            BufferError::VarIntOverflow => write!(f, "VarInt Overflow"),
+            BufferError::TrailingBytes(left) => write!(f, "Trailing Bytes: {left} left unread"),
+            BufferError::OutOfRange => write!(f, "Value Out of Range"),
+            BufferError::InvalidChecksum => write!(f, "Invalid Checksum"),
+            BufferError::NonCanonicalNaN => write!(f, "Non-Canonical NaN"),
+            BufferError::InvalidBool(byte) => write!(f, "Invalid Bool: {byte}"),
+            BufferError::StringRejected => write!(f, "String Rejected by Validator"),
+            BufferError::StringTooLong(max) => {
+                write!(f, "String Too Long: no terminator within {max} bytes")
+            }
+            BufferError::DuplicateEntry => write!(f, "Duplicate Entry"),
+            BufferError::UnsortedEntries => write!(f, "Unsorted Entries"),
+            BufferError::CapacityExceeded => write!(f, "Capacity Exceeded"),
+            BufferError::StalePlaceholder => write!(f, "Stale Placeholder"),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for BufferError {
+    fn from(e: FromUtf8Error) -> Self {
+        BufferError::InvalidString(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BufferError::InvalidString(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = core::result::Result<T, BufferError>;
+
+//Buffer's writers are BufWrite's, which can't fail when writing to a Vec, so they don't return a
+//Result. The ones that can fail for any writer, e.g. write_u24, are written out with a Result.
+macro_rules! write_through {
+    ($($fn:ident: $ty:ty),*) => {
+        $(
+            pub fn $fn(&mut self, data: $ty) {
+                BufWrite::$fn(self, data).expect("writing to a Buffer can't fail");
+            }
+        )*
+    };
+}
+
+//Buffer's readers are BufRead's, run on the slice that's left, so every reader decodes the same
+//way. Unlike BufRead's, they don't move the offset when they fail.
+macro_rules! read_through {
+    ($($fn:ident: $ty:ty),*) => {
+        $(
+            pub fn $fn(&mut self) -> Result<$ty> {
+                self.read_with(|rest| BufRead::$fn(rest))
+            }
+        )*
+    };
+}
 
 //Our version of Buffer that is implemented in bio - > https://github.com/bcoin-org/bufio
-#[derive(Default, PartialEq, Clone, Eq)]
+#[derive(Default, Clone)]
 pub struct Buffer {
     data: Vec<u8>,
     offset: usize,
+    //Placeholders reserved so far, numbering each one.
+    reserved: usize,
+    //The varint placeholders waiting to be patched, innermost last.
+    pending: Vec<usize>,
 }
 
 impl Buffer {
@@ -27,134 +116,251 @@ impl Buffer {
         Buffer::default()
     }
 
+    //Preallocates, e.g. with the encoded_len of what will be written.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Buffer::from(Vec::with_capacity(capacity))
+    }
+
     //Accept anything that implements into buffer.
     // pub fn new_with() -> Self {
 
     // }
 
     //Unsigned Integers - Little Endian
-    pub fn write_u8(&mut self, data: u8) {
-        self.data.extend_from_slice(&data.to_le_bytes());
-    }
+    write_through!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_u256: Uint256
+    );
 
-    pub fn write_u16(&mut self, data: u16) {
-        self.data.extend_from_slice(&data.to_le_bytes());
-    }
+    //Big Endian
+    write_through!(
+        write_u8_be: u8, write_u16_be: u16, write_u32_be: u32, write_u64_be: u64,
+        write_u128_be: u128, write_u256_be: Uint256
+    );
 
-    pub fn write_u32(&mut self, data: u32) {
-        self.data.extend_from_slice(&data.to_le_bytes());
-    }
+    //Signed Integers
+    write_through!(write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128);
 
-    pub fn write_u64(&mut self, data: u64) {
-        self.data.extend_from_slice(&data.to_le_bytes());
-    }
+    //Big Endian
+    write_through!(
+        write_i8_be: i8, write_i16_be: i16, write_i32_be: i32, write_i64_be: i64,
+        write_i128_be: i128
+    );
 
-    //TODO u128
+    //Floats are written as their IEEE 754 bits. Every NaN is written as the canonical quiet NaN, so
+    //equal values always have the same encoding.
+    write_through!(write_f32: f32, write_f32_be: f32, write_f64: f64, write_f64_be: f64);
 
-    pub fn write_u256(&mut self, data: Uint256) {
-        self.data.extend_from_slice(&data.to_le_bytes());
-    }
+    write_through!(write_bool: bool);
 
-    //Big Endian
-    pub fn write_u8_be(&mut self, data: u8) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    //24 and 48 bit integers, which error with OutOfRange if the value doesn't fit.
+    pub fn write_u24(&mut self, data: u32) -> Result<()> {
+        BufWrite::write_u24(self, data)
     }
 
-    pub fn write_u16_be(&mut self, data: u16) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    pub fn write_u24_be(&mut self, data: u32) -> Result<()> {
+        BufWrite::write_u24_be(self, data)
     }
 
-    pub fn write_u32_be(&mut self, data: u32) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    pub fn write_u48(&mut self, data: u64) -> Result<()> {
+        BufWrite::write_u48(self, data)
     }
 
-    pub fn write_u64_be(&mut self, data: u64) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    pub fn write_u48_be(&mut self, data: u64) -> Result<()> {
+        BufWrite::write_u48_be(self, data)
     }
 
-    //TODO u128, and u256
+    pub fn write_i24(&mut self, data: i32) -> Result<()> {
+        BufWrite::write_i24(self, data)
+    }
 
-    //Signed Integers
-    pub fn write_i8(&mut self, data: i8) {
-        self.data.extend_from_slice(&data.to_le_bytes());
+    pub fn write_i24_be(&mut self, data: i32) -> Result<()> {
+        BufWrite::write_i24_be(self, data)
     }
 
-    pub fn write_i16(&mut self, data: i16) {
-        self.data.extend_from_slice(&data.to_le_bytes());
+    pub fn write_i48(&mut self, data: i64) -> Result<()> {
+        BufWrite::write_i48(self, data)
     }
 
-    pub fn write_i32(&mut self, data: i32) {
-        self.data.extend_from_slice(&data.to_le_bytes());
+    pub fn write_i48_be(&mut self, data: i64) -> Result<()> {
+        BufWrite::write_i48_be(self, data)
     }
 
-    pub fn write_i64(&mut self, data: i64) {
-        self.data.extend_from_slice(&data.to_le_bytes());
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
     }
 
-    //Big Endian
-    pub fn write_i8_be(&mut self, data: i8) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    write_through!(write_var_bytes: &[u8], write_str: &str, write_string: String);
+
+    write_through!(write_var_string: &str);
+
+    //Errors with StringRejected, writing nothing, if the validator rejects string.
+    pub fn write_var_string_with<V: StringValidator>(
+        &mut self,
+        string: &str,
+        validator: V,
+    ) -> Result<()> {
+        BufWrite::write_var_string_with(self, string, validator)
     }
 
-    pub fn write_i16_be(&mut self, data: i16) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    //Writes the string followed by a null byte, so it can't contain one itself.
+    pub fn write_cstring(&mut self, string: &str) -> Result<()> {
+        BufWrite::write_cstring(self, string)
     }
 
-    pub fn write_i32_be(&mut self, data: i32) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    write_through!(write_hash: Hash, write_varint: usize);
+
+    //Bitcoin Core's VARINT, see CoreVarInt.
+    write_through!(write_varint2: u64);
+
+    //LEB128, as used by protobuf and WASM. Least significant group of 7 bits first, with the high
+    //bit set on every byte but the last.
+    write_through!(write_uleb128: u64, write_uleb128_u128: u128);
+
+    //Signed LEB128, the last byte is sign extended.
+    write_through!(write_sleb128: i64, write_sleb128_i128: i128);
+
+    //Zigzag maps small negative numbers to small unsigned ones (0, -1, 1, -2 => 0, 1, 2, 3) which
+    //are then written as unsigned LEB128, like protobuf's sint64.
+    write_through!(write_zigzag: i64, write_zigzag_i128: i128);
+
+    fn reserve(&mut self, kind: PlaceholderKind) -> Placeholder {
+        self.reserved += 1;
+        Placeholder {
+            offset: self.data.len(),
+            kind,
+            id: self.reserved,
+            below: self.pending.last().copied(),
+        }
     }
 
-    pub fn write_i64_be(&mut self, data: i64) {
-        self.data.extend_from_slice(&data.to_be_bytes());
+    //Writes 4 zero bytes to be filled in later with patch.
+    pub fn reserve_u32(&mut self) -> Placeholder {
+        let placeholder = self.reserve(PlaceholderKind::U32);
+        self.write_u32(0);
+        placeholder
     }
 
-    pub fn write_bytes(&mut self, bytes: &[u8]) {
-        //TODO should we clone here or just pass in
-        self.data.extend_from_slice(bytes);
+    //A varint's size depends on its value, so nothing is written until it is patched, and patch
+    //then inserts it, moving everything after it. Varint placeholders have to be patched in the
+    //reverse order they were reserved, like nested length prefixes are, and any placeholder
+    //reserved after one has to be patched before it. Patching out of that order errors with
+    //StalePlaceholder instead of writing at an offset that has moved, and one that is never
+    //patched leaves those reserved before it stale too. Offsets taken past it, e.g. by a
+    //Checkpoint, move as well.
+    pub fn reserve_varint(&mut self) -> Placeholder {
+        let placeholder = self.reserve(PlaceholderKind::VarInt);
+        self.pending.push(placeholder.id);
+        placeholder
     }
 
-    pub fn write_var_bytes(&mut self, bytes: &[u8]) {
-        self.write_varint(bytes.len());
+    pub fn patch(&mut self, placeholder: Placeholder, value: usize) -> Result<()> {
+        //Varints pending when a placeholder was reserved are inserted before it, so it has moved
+        //once the innermost of those has been patched.
+        let stale = match placeholder.kind {
+            PlaceholderKind::U32 => {
+                matches!(placeholder.below, Some(below) if !self.pending.contains(&below))
+            }
+            PlaceholderKind::VarInt => self.pending.last() != Some(&placeholder.id),
+        };
+        if stale {
+            return Err(BufferError::StalePlaceholder);
+        }
 
-        if bytes.is_empty() {
-            return;
+        match placeholder.kind {
+            PlaceholderKind::U32 => {
+                let value = u32::try_from(value).map_err(|_| BufferError::OutOfRange)?;
+                let range = placeholder.offset..placeholder.offset + 4;
+                self.data
+                    .get_mut(range)
+                    .ok_or(BufferError::OutOfBounds)?
+                    .copy_from_slice(&value.to_le_bytes());
+            }
+            PlaceholderKind::VarInt => {
+                if placeholder.offset > self.data.len() {
+                    return Err(BufferError::OutOfBounds);
+                }
+                let (bytes, len) = VarInt::from(value).encode();
+                self.data.splice(
+                    placeholder.offset..placeholder.offset,
+                    bytes[..len].iter().cloned(),
+                );
+                self.pending.pop();
+            }
         }
 
-        self.data.extend_from_slice(bytes);
+        Ok(())
     }
 
-    pub fn write_str(&mut self, string: &str) {
-        self.data.extend_from_slice(string.as_bytes());
-    }
+    //Writes whatever f writes, prefixed with its length as a varint. Errors with StalePlaceholder
+    //if f leaves a varint placeholder of its own unpatched, and with OutOfBounds if f truncates
+    //the buffer past where it started.
+    pub fn write_length_prefixed<F, R>(&mut self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Buffer) -> R,
+    {
+        let placeholder = self.reserve_varint();
+        let start = self.data.len();
+
+        let ret = f(self);
+
+        let length = self
+            .data
+            .len()
+            .checked_sub(start)
+            .ok_or(BufferError::OutOfBounds)?;
+        self.patch(placeholder, length)?;
 
-    pub fn write_string(&mut self, string: String) {
-        self.data.extend_from_slice(string.as_bytes());
+        Ok(ret)
     }
 
-    pub fn write_hash(&mut self, hash: Hash) {
-        self.data.extend(hash.to_array());
+    //Appends the first 4 bytes of the double SHA-256 of range, e.g. buffer.write_checksum(start..)
+    pub fn write_checksum<R>(&mut self, range: R) -> Result<()>
+    where
+        R: SliceIndex<[u8], Output = [u8]>,
+    {
+        let checksum = base58::checksum(self.data.get(range).ok_or(BufferError::OutOfBounds)?);
+        self.write_bytes(&checksum);
+
+        Ok(())
     }
 
-    pub fn write_varint(&mut self, data: usize) {
-        if data < 0xFD {
-            self.write_u8(data as u8);
-            return;
-        }
+    //Reads a checksum written by write_checksum, and checks it against range.
+    pub fn read_checksum<R>(&mut self, range: R) -> Result<()>
+    where
+        R: SliceIndex<[u8], Output = [u8]>,
+    {
+        let expected = base58::checksum(self.data.get(range).ok_or(BufferError::OutOfBounds)?);
 
-        if data < 0xFFFF {
-            self.write_u8(0xFD);
-            self.write_u16(data as u16);
-            return;
+        if self.read_bytes(4)? != expected {
+            return Err(BufferError::InvalidChecksum);
         }
 
-        if data < 0xFFFFFFFF {
-            self.write_u8(0xFE);
-            self.write_u32(data as u32);
-            return;
-        }
+        Ok(())
+    }
+
+    //Collections are written as a varint count followed by each item. The closures return a
+    //Result so the same encoder can be used with any BufWrite, and the first error is returned.
+    pub fn write_vec<T, F>(&mut self, items: &[T], f: F) -> Result<()>
+    where
+        F: FnMut(&mut Buffer, &T) -> Result<()>,
+    {
+        BufWrite::write_vec(self, items, f)
+    }
+
+    //Entries are written in key order, which is the only order read_map accepts.
+    pub fn write_map<K, V, F>(&mut self, map: &BTreeMap<K, V>, f: F) -> Result<()>
+    where
+        F: FnMut(&mut Buffer, &K, &V) -> Result<()>,
+    {
+        BufWrite::write_map(self, map, f)
+    }
 
-        self.write_u8(0xFF);
-        self.write_u64(data as u64);
+    pub fn write_set<T, F>(&mut self, set: &BTreeSet<T>, f: F) -> Result<()>
+    where
+        F: FnMut(&mut Buffer, &T) -> Result<()>,
+    {
+        BufWrite::write_set(self, set, f)
     }
 
     pub fn fill(&mut self, value: u8, amount: usize) {
@@ -171,225 +377,467 @@ impl Buffer {
         self.data.extend_from_slice(slice);
     }
 
-    ////Return Hex string of the buffer, Consumes the Buffer
+    //Return Hex string of the buffer, Consumes the Buffer
     pub fn into_hex(self) -> String {
-        self.data.to_hex()
+        hex::encode(&self.data)
+    }
+
+    pub fn to_base58(&self) -> String {
+        base58::encode(&self.data)
+    }
+
+    pub fn from_base58(encoded: &str) -> core::result::Result<Self, Base58Error> {
+        Ok(Buffer::from(base58::decode(encoded)?))
+    }
+
+    //Appends a 4 byte double SHA-256 checksum, as used by P2PKH and P2SH addresses.
+    pub fn to_base58check(&self) -> String {
+        base58::encode_check(&self.data)
+    }
+
+    pub fn from_base58check(encoded: &str) -> core::result::Result<Self, Base58Error> {
+        Ok(Buffer::from(base58::decode_check(encoded)?))
+    }
+
+    //Regroups the bytes into 5 bit values and encodes them under hrp.
+    pub fn to_bech32(
+        &self,
+        hrp: &str,
+        variant: Variant,
+    ) -> core::result::Result<String, Bech32Error> {
+        let data = bech32::convert_bits(&self.data, 8, 5, true)?;
+        bech32::encode(hrp, &data, variant)
+    }
+
+    //Returns the hrp, the bytes and the variant the checksum matched.
+    pub fn from_bech32(
+        encoded: &str,
+    ) -> core::result::Result<(String, Self, Variant), Bech32Error> {
+        let (hrp, data, variant) = bech32::decode(encoded)?;
+        let bytes = bech32::convert_bits(&data, 5, 8, false)?;
+        Ok((hrp, Buffer::from(bytes), variant))
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.data)
+    }
+
+    pub fn from_base64(encoded: &str) -> core::result::Result<Self, Base64Error> {
+        Ok(Buffer::from(base64::decode(encoded)?))
+    }
+
+    //URL-safe alphabet, without padding.
+    pub fn to_base64_url(&self) -> String {
+        base64::encode_url(&self.data)
+    }
+
+    pub fn from_base64_url(encoded: &str) -> core::result::Result<Self, Base64Error> {
+        Ok(Buffer::from(base64::decode_url(encoded)?))
+    }
+
+    //Lowercase and without padding, so it can be used as a DNS label.
+    pub fn to_base32(&self) -> String {
+        base32::encode(&self.data)
+    }
+
+    pub fn from_base32(encoded: &str) -> core::result::Result<Self, Base32Error> {
+        Ok(Buffer::from(base32::decode(encoded)?))
+    }
+
+    pub fn to_zbase32(&self) -> String {
+        base32::encode_zbase32(&self.data)
+    }
+
+    pub fn from_zbase32(encoded: &str) -> core::result::Result<Self, Base32Error> {
+        Ok(Buffer::from(base32::decode_zbase32(encoded)?))
     }
 
     //Check for length
     pub fn check(&self, size: usize) -> Result<()> {
-        if self.offset + size > self.data.len() {
+        //Written this way round so a huge size can't overflow.
+        if size > self.remaining() {
             return Err(BufferError::OutOfBounds);
         }
         Ok(())
     }
 
-    //These can probably all be macro'd out.
-    pub fn read_u8(&mut self) -> Result<u8> {
-        self.check(1)?;
-        let result = self.data[self.offset];
+    //Reads through the slice that's left, only moving the offset past what f read if it succeeds,
+    //so a failed read leaves the offset where it was.
+    fn read_with<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut &[u8]) -> Result<T>,
+    {
+        let mut rest = &self.data[self.offset.min(self.data.len())..];
+        let before = rest.len();
 
-        self.offset += 1;
+        let value = f(&mut rest)?;
+        let read = before - rest.len();
 
-        Ok(result)
+        self.offset += read;
+        Ok(value)
     }
 
-    pub fn read_u16(&mut self) -> Result<u16> {
-        self.check(2)?;
-        let range = self.offset..self.offset + 2;
+    read_through!(read_u8: u8, read_u16: u16, read_u16_be: u16);
+    read_through!(read_u32: u32, read_u32_be: u32, read_u64: u64, read_u64_be: u64);
+    read_through!(read_u128: u128, read_u128_be: u128, read_i128: i128, read_i128_be: i128);
+    read_through!(read_u256: Uint256, read_u256_be: Uint256);
 
-        let mut buf = [0; 2];
-        buf.copy_from_slice(&self.data[range]);
+    //Rejects any NaN other than the canonical one write_f32 produces, leaving it unread.
+    read_through!(read_f32: f32, read_f32_be: f32, read_f64: f64, read_f64_be: f64);
 
-        let ret = u16::from_le_bytes(buf);
-
-        self.offset += 2;
-
-        Ok(ret)
-    }
+    //Only 0 and 1 are accepted, anything else is left unread.
+    read_through!(read_bool: bool);
 
-    pub fn read_u16_be(&mut self) -> Result<u16> {
-        self.check(2)?;
-        let range = self.offset..self.offset + 2;
+    read_through!(read_u24: u32, read_u24_be: u32, read_u48: u64, read_u48_be: u64);
 
-        let mut buf = [0; 2];
-        buf.copy_from_slice(&self.data[range]);
+    //Sign extended from the top bit that was read.
+    read_through!(read_i24: i32, read_i24_be: i32, read_i48: i64, read_i48_be: i64);
 
-        let ret = u16::from_be_bytes(buf);
+    read_through!(read_hash: Hash, read_varint: VarInt);
 
-        self.offset += 2;
+    read_through!(read_varint2: CoreVarInt);
 
-        Ok(ret)
-    }
+    read_through!(read_uleb128: u64, read_uleb128_u128: u128);
 
-    //TODO do we see any need for reading u24s, 48s, etc?
+    read_through!(read_sleb128: i64, read_sleb128_i128: i128);
 
-    pub fn read_u32(&mut self) -> Result<u32> {
-        self.check(4)?;
-        let range = self.offset..self.offset + 4;
+    read_through!(read_zigzag: i64, read_zigzag_i128: i128);
 
-        let mut buf = [0; 4];
-        buf.copy_from_slice(&self.data[range]);
+    pub fn read_string(&mut self, size: usize) -> Result<String> {
+        self.read_with(|rest| rest.read_string(size))
+    }
 
-        let ret = u32::from_le_bytes(buf);
+    read_through!(read_var_string: String);
 
-        self.offset += 4;
+    //The offset isn't moved if the string is invalid.
+    pub fn read_var_string_with<V: StringValidator>(&mut self, validator: V) -> Result<String> {
+        self.read_with(|rest| rest.read_var_string_with(validator))
+    }
 
-        Ok(ret)
+    //Reads up to and including a null byte, which has to come within max_len bytes of the
+    //string's start. The null byte isn't part of the returned string.
+    pub fn read_cstring(&mut self, max_len: usize) -> Result<String> {
+        self.read_with(|rest| rest.read_cstring(max_len))
     }
 
-    pub fn read_u32_be(&mut self) -> Result<u32> {
-        self.check(4)?;
-        let range = self.offset..self.offset + 4;
+    pub fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>> {
+        self.read_with(|rest| rest.read_bytes(size))
+    }
 
-        let mut buf = [0; 4];
-        buf.copy_from_slice(&self.data[range]);
+    read_through!(read_var_bytes: Vec<u8>);
 
-        let ret = u32::from_be_bytes(buf);
+    //Reads past the next len bytes and borrows them, so a nested decoder reading them through
+    //BufRead gets OutOfBounds instead of reading into whatever follows it. Nothing is copied.
+    pub fn read_sub_buffer(&mut self, len: usize) -> Result<&[u8]> {
+        self.check(len)?;
 
-        self.offset += 4;
+        let range = self.offset..self.offset + len;
+        self.offset += len;
 
-        Ok(ret)
+        Ok(&self.data[range])
     }
 
-    pub fn read_u64(&mut self) -> Result<u64> {
-        self.check(8)?;
-        let range = self.offset..self.offset + 8;
+    pub fn read_var_sub_buffer(&mut self) -> Result<&[u8]> {
+        let length = self.read_varint()?;
+        let size = usize::try_from(length).map_err(|_| BufferError::OutOfBounds)?;
 
-        let mut buf = [0; 8];
-        buf.copy_from_slice(&self.data[range]);
+        self.read_sub_buffer(size)
+    }
 
-        let ret = u64::from_le_bytes(buf);
+    //Errors if anything is left unread, e.g. after decoding a sub buffer.
+    pub fn expect_consumed(&self) -> Result<()> {
+        match self.remaining() {
+            0 => Ok(()),
+            left => Err(BufferError::TrailingBytes(left)),
+        }
+    }
 
-        self.offset += 8;
+    pub fn read_vec<T, F>(&mut self, f: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Buffer) -> Result<T>,
+    {
+        BufRead::read_vec(self, f)
+    }
 
-        Ok(ret)
+    //Errors with DuplicateEntry or UnsortedEntries unless the keys are in strictly increasing
+    //order, so every map has one encoding.
+    pub fn read_map<K, V, F>(&mut self, f: F) -> Result<BTreeMap<K, V>>
+    where
+        K: Ord,
+        F: FnMut(&mut Buffer) -> Result<(K, V)>,
+    {
+        BufRead::read_map(self, f)
     }
 
-    pub fn read_u64_be(&mut self) -> Result<u64> {
-        self.check(8)?;
-        let range = self.offset..self.offset + 8;
+    //With unique set, a repeated item errors with DuplicateEntry instead of being merged. Items
+    //can come in any order.
+    pub fn read_set<T, F>(&mut self, unique: bool, f: F) -> Result<BTreeSet<T>>
+    where
+        T: Ord,
+        F: FnMut(&mut Buffer) -> Result<T>,
+    {
+        BufRead::read_set(self, unique, f)
+    }
+
+    //Essentially shifts the offset to offset += off
+    pub fn seek(&mut self, off: usize) -> Result<()> {
+        self.check(off)?;
+
+        self.offset += off;
 
-        let mut buf = [0; 8];
-        buf.copy_from_slice(&self.data[range]);
+        Ok(())
+    }
 
-        let ret = u64::from_be_bytes(buf);
+    //Shifts the offset to offset -= off
+    pub fn seek_back(&mut self, off: usize) -> Result<()> {
+        if off > self.offset {
+            return Err(BufferError::OutOfBounds);
+        }
 
-        self.offset += 8;
+        self.offset -= off;
 
-        Ok(ret)
+        Ok(())
     }
 
-    pub fn read_u256(&mut self) -> Result<Uint256> {
-        self.check(32)?;
+    pub fn set_offset(&mut self, offset: usize) -> Result<()> {
+        if offset > self.data.len() {
+            return Err(BufferError::OutOfBounds);
+        }
 
-        let range = self.offset..self.offset + 32;
-        let ret = Uint256::from_bytes(&self.data[range]);
+        self.offset = offset;
 
-        self.offset += 32;
+        Ok(())
+    }
 
-        Ok(ret)
+    pub fn rewind(&mut self) {
+        self.offset = 0;
     }
 
-    pub fn read_varint(&mut self) -> Result<VarInt> {
-        let len = self.read_u8()?;
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 
-        match len {
-            0xFF => {
-                let num = self.read_u64()?;
-                if num < 0x100000000 {
-                    Err(BufferError::NonMinimalVarInt)
-                } else {
-                    Ok(VarInt::from(num))
-                }
-            }
-            0xFE => {
-                let num = self.read_u32()?;
-                if num < 0x10000 {
-                    Err(BufferError::NonMinimalVarInt)
-                } else {
-                    Ok(VarInt::from(num))
-                }
-            }
-            0xFD => {
-                let num = self.read_u16()?;
-                if num < 0xFD {
-                    Err(BufferError::NonMinimalVarInt)
-                } else {
-                    Ok(VarInt::from(num))
-                }
-            }
+    //Bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
 
-            len => Ok(VarInt::from(len)),
+    //Saves the offset, which is restored when the guard is dropped unless it is committed. The
+    //guard derefs to the Buffer, so a parse can be tried and abandoned on any error, e.g.
+    //
+    //    let mut attempt = buffer.checkpoint();
+    //    let version = attempt.read_u8()?;
+    //    attempt.commit();
+    pub fn checkpoint(&mut self) -> Checkpoint<'_> {
+        Checkpoint {
+            offset: self.offset,
+            buffer: self,
+            committed: false,
         }
     }
 
-    pub fn read_string(&mut self, size: usize) -> Result<String> {
+    peek!(peek_u8, read_u8, u8);
+    peek!(peek_u16, read_u16, u16);
+    peek!(peek_u16_be, read_u16_be, u16);
+    peek!(peek_u32, read_u32, u32);
+    peek!(peek_u32_be, read_u32_be, u32);
+    peek!(peek_u64, read_u64, u64);
+    peek!(peek_u64_be, read_u64_be, u64);
+    peek!(peek_u256, read_u256, Uint256);
+    peek!(peek_u256_be, read_u256_be, Uint256);
+    peek!(peek_varint, read_varint, VarInt);
+    peek!(peek_hash, read_hash, Hash);
+
+    pub fn peek_bytes(&self, size: usize) -> Result<&[u8]> {
         self.check(size)?;
 
-        let range = self.offset..self.offset + size;
-        let ret = String::from_utf8(self.data[range].to_vec())?;
+        Ok(&self.data[self.offset..self.offset + size])
+    }
 
-        self.offset += size;
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.data
+    }
 
-        Ok(ret)
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.data.clone()
     }
+}
 
-    pub fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>> {
-        self.check(size)?;
+//Decides which strings write_var_string_with and read_var_string_with accept. Closures work as
+//validators, and a tuple of validators accepts what all of them accept, e.g.
+//(Printable, MaxLength(255)).
+pub trait StringValidator {
+    fn validate(&self, string: &str) -> bool;
+}
 
-        let range = self.offset..self.offset + size;
-        let ret = self.data[range].to_vec();
+impl<F: Fn(&str) -> bool> StringValidator for F {
+    fn validate(&self, string: &str) -> bool {
+        self(string)
+    }
+}
 
-        self.offset += size;
+impl<A: StringValidator, B: StringValidator> StringValidator for (A, B) {
+    fn validate(&self, string: &str) -> bool {
+        self.0.validate(string) && self.1.validate(string)
+    }
+}
 
-        Ok(ret)
+//Only ASCII characters.
+#[derive(Debug, Clone, Copy)]
+pub struct Ascii;
+
+impl StringValidator for Ascii {
+    fn validate(&self, string: &str) -> bool {
+        string.is_ascii()
     }
+}
 
-    pub fn read_var_bytes(&mut self) -> Result<Vec<u8>> {
-        let length = self.read_varint()?;
+//Only printable ASCII, 0x20 (space) to 0x7E.
+#[derive(Debug, Clone, Copy)]
+pub struct Printable;
 
-        //TODO make an as_usize for varint.
-        let size = length.as_u64() as usize;
+impl StringValidator for Printable {
+    fn validate(&self, string: &str) -> bool {
+        string.bytes().all(|byte| (0x20..=0x7E).contains(&byte))
+    }
+}
 
-        let range = self.offset..self.offset + size;
-        let ret = self.data[range].to_vec();
+//At most this many bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxLength(pub usize);
 
-        self.offset += size;
+impl StringValidator for MaxLength {
+    fn validate(&self, string: &str) -> bool {
+        string.len() <= self.0
+    }
+}
 
-        Ok(ret)
+//A Handshake name, following hsd's rules: 1 to 63 characters of a-z, 0-9, - and _, not starting
+//or ending with - or _.
+#[derive(Debug, Clone, Copy)]
+pub struct HandshakeName;
+
+impl StringValidator for HandshakeName {
+    fn validate(&self, string: &str) -> bool {
+        let bytes = string.as_bytes();
+        if bytes.is_empty() || bytes.len() > 63 {
+            return false;
+        }
+
+        let last = bytes.len() - 1;
+        bytes.iter().enumerate().all(|(i, &byte)| match byte {
+            b'a'..=b'z' | b'0'..=b'9' => true,
+            b'-' | b'_' => i != 0 && i != last,
+            _ => false,
+        })
+    }
+}
+
+const CANONICAL_NAN_F32: u32 = 0x7FC0_0000;
+const CANONICAL_NAN_F64: u64 = 0x7FF8_0000_0000_0000;
+
+pub(crate) fn canonical_f32(num: f32) -> f32 {
+    if num.is_nan() {
+        f32::from_bits(CANONICAL_NAN_F32)
+    } else {
+        num
+    }
+}
+
+pub(crate) fn canonical_f64(num: f64) -> f64 {
+    if num.is_nan() {
+        f64::from_bits(CANONICAL_NAN_F64)
+    } else {
+        num
     }
+}
 
-    pub fn read_hash(&mut self) -> Result<Hash> {
-        self.check(32)?;
+pub(crate) fn check_f32(bits: u32) -> Result<f32> {
+    let num = f32::from_bits(bits);
+    if num.is_nan() && bits != CANONICAL_NAN_F32 {
+        return Err(BufferError::NonCanonicalNaN);
+    }
+    Ok(num)
+}
 
-        let mut array = [0; 32];
+pub(crate) fn check_f64(bits: u64) -> Result<f64> {
+    let num = f64::from_bits(bits);
+    if num.is_nan() && bits != CANONICAL_NAN_F64 {
+        return Err(BufferError::NonCanonicalNaN);
+    }
+    Ok(num)
+}
 
-        let range = self.offset..self.offset + 32;
-        array.copy_from_slice(&self.data[range]);
+//Returned by Buffer::reserve_u32 and Buffer::reserve_varint, and used up by Buffer::patch.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Placeholder {
+    offset: usize,
+    kind: PlaceholderKind,
+    id: usize,
+    //The innermost varint placeholder pending when this one was reserved.
+    below: Option<usize>,
+}
 
-        let hash = Hash::from(array);
+#[derive(Debug, PartialEq, Eq)]
+enum PlaceholderKind {
+    U32,
+    VarInt,
+}
 
-        self.offset += 32;
+//Returned by Buffer::checkpoint.
+pub struct Checkpoint<'a> {
+    buffer: &'a mut Buffer,
+    offset: usize,
+    committed: bool,
+}
 
-        Ok(hash)
+impl<'a> Checkpoint<'a> {
+    //Keeps everything read since the checkpoint.
+    pub fn commit(mut self) {
+        self.committed = true;
     }
+}
 
-    //Essentially shifts the offset to offset += off
-    pub fn seek(&mut self, off: usize) -> Result<()> {
-        self.check(off)?;
+impl<'a> ops::Deref for Checkpoint<'a> {
+    type Target = Buffer;
 
-        self.offset += off;
+    fn deref(&self) -> &Buffer {
+        self.buffer
+    }
+}
 
-        Ok(())
+impl<'a> ops::DerefMut for Checkpoint<'a> {
+    fn deref_mut(&mut self) -> &mut Buffer {
+        self.buffer
     }
+}
 
-    pub fn to_bytes(&self) -> &[u8] {
-        &self.data
+impl<'a> Drop for Checkpoint<'a> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.buffer.offset = self.offset;
+        }
     }
+}
 
-    pub fn to_vec(&self) -> Vec<u8> {
-        self.data.clone()
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Buffer {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Buffer::from(Vec::<u8>::arbitrary(u)?))
+    }
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for Buffer {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Buffer>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        prop::collection::vec(any::<u8>(), 0..512)
+            .prop_map(Buffer::from)
+            .boxed()
     }
 }
 
@@ -397,20 +845,26 @@ impl From<Vec<u8>> for Buffer {
     fn from(buf: Vec<u8>) -> Self {
         Buffer {
             data: buf,
-            offset: 0,
+            ..Default::default()
         }
     }
 }
 
 impl From<&[u8]> for Buffer {
     fn from(buf: &[u8]) -> Self {
-        Buffer {
-            data: buf.to_vec(),
-            offset: 0,
-        }
+        Buffer::from(buf.to_vec())
     }
 }
 
+//Only the bytes and the offset take part, not the placeholder bookkeeping.
+impl PartialEq for Buffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset && self.data == other.data
+    }
+}
+
+impl Eq for Buffer {}
+
 //@todo commenting out for now. Not sure how we should implement this. FromStr technically should
 //return an error, but writing a string to a Buffer will never error out. Also it might add some
 //confusion as to whether we are creating the buffer from the raw string or from a hex string. So
@@ -425,7 +879,7 @@ impl From<&[u8]> for Buffer {
 //    }
 //}
 
-////TODO review, seems inefficent
+//TODO review, seems inefficent
 //impl From<String> for Buffer {
 //    fn from(buf: String) -> Self {
 //        Buffer {
@@ -471,17 +925,42 @@ impl AsMut<[u8]> for Buffer {
     }
 }
 
+//Reads from the offset, like the `read_*` methods, and writes append.
+#[cfg(feature = "std")]
+impl std::io::Read for Buffer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.remaining());
+        buf[..len].copy_from_slice(&self.data[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
 impl FromHex for Buffer {
     type Error = FromHexError;
 
-    fn from_hex<T: AsRef<[u8]>>(hex: T) -> std::result::Result<Self, Self::Error> {
-        Ok(Buffer::from(Vec::from_hex(hex)?))
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> core::result::Result<Self, Self::Error> {
+        Ok(Buffer::from(hex::decode(hex)?))
     }
 }
 
+#[cfg(feature = "std")]
 impl ToHex for Buffer {
     fn to_hex(&self) -> String {
-        self.data.to_hex()
+        hex::encode(&self.data)
     }
 }
 
@@ -491,7 +970,7 @@ impl fmt::Display for Buffer {
             f,
             "Offset: {}, Buffer: {})",
             self.offset,
-            self.data.to_hex(),
+            hex::encode(&self.data),
         )
     }
 }
@@ -502,16 +981,16 @@ impl fmt::Debug for Buffer {
             f,
             "Offset: {}, Buffer: {})",
             self.offset,
-            self.data.to_hex(),
+            hex::encode(&self.data),
         )
     }
 }
 
 #[cfg(feature = "serialization")]
 impl serde::Serialize for Buffer {
-    fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
         if s.is_human_readable() {
-            s.serialize_str(&self.to_hex())
+            s.serialize_str(&hex::encode(&self.data))
         } else {
             s.serialize_bytes(&self[..])
         }
@@ -520,33 +999,33 @@ impl serde::Serialize for Buffer {
 
 #[cfg(feature = "serialization")]
 impl<'de> serde::Deserialize<'de> for Buffer {
-    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Buffer, D::Error> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Buffer, D::Error> {
         if d.is_human_readable() {
             struct HexVisitor;
 
             impl<'de> serde::de::Visitor<'de> for HexVisitor {
                 type Value = Buffer;
 
-                fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     formatter.write_str("an ASCII hex string")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    if let Ok(hex) = ::std::str::from_utf8(v) {
-                        Buffer::from_hex(hex).map_err(E::custom)
+                    if let Ok(string) = ::core::str::from_utf8(v) {
+                        hex::decode(string).map(Buffer::from).map_err(E::custom)
                     } else {
-                        return Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self));
+                        Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self))
                     }
                 }
 
-                fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+                fn visit_str<E>(self, v: &str) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    Buffer::from_hex(v).map_err(E::custom)
+                    hex::decode(v).map(Buffer::from).map_err(E::custom)
                 }
             }
 
@@ -557,11 +1036,11 @@ impl<'de> serde::Deserialize<'de> for Buffer {
             impl<'de> ::serde::de::Visitor<'de> for BytesVisitor {
                 type Value = Buffer;
 
-                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                     formatter.write_str("a bytestring")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
@@ -577,6 +1056,27 @@ impl<'de> serde::Deserialize<'de> for Buffer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(BufferError::OutOfBounds.to_string(), "Read Out of Bounds");
+        assert_eq!(
+            BufferError::TrailingBytes(3).to_string(),
+            "Trailing Bytes: 3 left unread"
+        );
+        assert_eq!(BufferError::InvalidBool(2).to_string(), "Invalid Bool: 2");
+        assert_eq!(
+            BufferError::StringTooLong(16).to_string(),
+            "String Too Long: no terminator within 16 bytes"
+        );
+
+        let mut buffer = Buffer::from([1, 0xFF].to_vec());
+        let err = buffer.read_var_string().unwrap_err();
+        assert!(matches!(err, BufferError::InvalidString(_)));
+        assert!(err.to_string().starts_with("invalid utf-8"));
+    }
 
     #[test]
     fn test_write_u32() {
@@ -589,6 +1089,180 @@ mod tests {
         assert_eq!(buffer, Buffer::from([21, 205, 91, 7].to_vec()));
     }
 
+    #[test]
+    fn test_u256() {
+        let num = Uint256::from_u64_const(0x0102);
+
+        let mut buffer = Buffer::new();
+
+        buffer.write_u256(num);
+        buffer.write_u256_be(num);
+
+        assert_eq!(buffer[0..2], [0x02, 0x01]);
+        assert_eq!(buffer[62..64], [0x01, 0x02]);
+
+        assert_eq!(buffer.read_u256().unwrap(), num);
+        assert_eq!(buffer.read_u256_be().unwrap(), num);
+        assert!(buffer.read_u256().is_err());
+    }
+
+    #[test]
+    fn test_varint2() {
+        //From Bitcoin Core's serialize_tests.
+        let vectors: &[(u64, &str)] = &[
+            (0, "00"),
+            (0x7f, "7f"),
+            (0x80, "8000"),
+            (0x1234, "a334"),
+            (0xffff, "82fe7f"),
+            (0x123456, "c7e756"),
+            (0x80123456, "86ffc7e756"),
+            (0xffffffff, "8efefefe7f"),
+            (0x7fffffffffffffff, "fefefefefefefefe7f"),
+            (0xffffffffffffffff, "80fefefefefefefefe7f"),
+        ];
+
+        for (num, hex) in vectors {
+            let mut buffer = Buffer::new();
+            buffer.write_varint2(*num);
+            assert_eq!(hex::encode(&buffer), *hex);
+            assert_eq!(CoreVarInt::from(*num).encoded_size() as usize, buffer.len());
+
+            assert_eq!(buffer.read_varint2().unwrap().to_u64(), *num);
+            assert!(buffer.read_u8().is_err());
+        }
+
+        //One past u64::MAX.
+        let mut buffer = Buffer::from(hex::decode("80fefefefefefefeff00").unwrap());
+        assert!(matches!(
+            buffer.read_varint2(),
+            Err(BufferError::VarIntOverflow)
+        ));
+
+        let mut buffer = Buffer::from(hex::decode("8080808080808080808000").unwrap());
+        assert!(matches!(
+            buffer.read_varint2(),
+            Err(BufferError::VarIntOverflow)
+        ));
+
+        let mut buffer = Buffer::from(hex::decode("8080").unwrap());
+        assert!(matches!(
+            buffer.read_varint2(),
+            Err(BufferError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_leb128() {
+        let mut buffer = Buffer::new();
+        buffer.write_uleb128(624485);
+        buffer.write_sleb128(-123456);
+        assert_eq!(hex::encode(&buffer), "e58e26c0bb78");
+        assert_eq!(buffer.read_uleb128().unwrap(), 624485);
+        assert_eq!(buffer.read_sleb128().unwrap(), -123456);
+
+        let signed: &[(i64, &str)] = &[
+            (0, "00"),
+            (63, "3f"),
+            (64, "c000"),
+            (-1, "7f"),
+            (-64, "40"),
+            (-65, "bf7f"),
+            (i64::MAX, "ffffffffffffffffff00"),
+            (i64::MIN, "8080808080808080807f"),
+        ];
+        for (num, hex) in signed {
+            let mut buffer = Buffer::new();
+            buffer.write_sleb128(*num);
+            assert_eq!(hex::encode(&buffer), *hex);
+            assert_eq!(buffer.read_sleb128().unwrap(), *num);
+        }
+
+        let mut buffer = Buffer::new();
+        buffer.write_uleb128(u64::MAX);
+        assert_eq!(hex::encode(&buffer), "ffffffffffffffffff01");
+        buffer.write_uleb128_u128(u128::MAX);
+        buffer.write_sleb128_i128(i128::MIN);
+        buffer.write_sleb128_i128(i128::MAX);
+        assert_eq!(buffer.read_uleb128().unwrap(), u64::MAX);
+        assert_eq!(buffer.read_uleb128_u128().unwrap(), u128::MAX);
+        assert_eq!(buffer.read_sleb128_i128().unwrap(), i128::MIN);
+        assert_eq!(buffer.read_sleb128_i128().unwrap(), i128::MAX);
+        assert!(buffer.read_u8().is_err());
+    }
+
+    #[test]
+    fn test_zigzag() {
+        let vectors: &[(i64, &str)] = &[
+            (0, "00"),
+            (-1, "01"),
+            (1, "02"),
+            (-2, "03"),
+            (i64::MAX, "feffffffffffffffff01"),
+            (i64::MIN, "ffffffffffffffffff01"),
+        ];
+        for (num, hex) in vectors {
+            let mut buffer = Buffer::new();
+            buffer.write_zigzag(*num);
+            assert_eq!(hex::encode(&buffer), *hex);
+            assert_eq!(buffer.read_zigzag().unwrap(), *num);
+        }
+
+        let mut buffer = Buffer::new();
+        buffer.write_zigzag_i128(i128::MIN);
+        buffer.write_zigzag_i128(-3);
+        assert_eq!(buffer.read_zigzag_i128().unwrap(), i128::MIN);
+        assert_eq!(buffer.read_zigzag_i128().unwrap(), -3);
+    }
+
+    #[test]
+    fn test_leb128_invalid() {
+        let read_uleb = |encoded: &str| Buffer::from(hex::decode(encoded).unwrap()).read_uleb128();
+        let read_sleb = |encoded: &str| Buffer::from(hex::decode(encoded).unwrap()).read_sleb128();
+
+        assert!(matches!(
+            read_uleb("8000"),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+        assert!(matches!(
+            read_uleb("ff00"),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+        assert!(matches!(
+            read_uleb("ffffffffffffffffff02"),
+            Err(BufferError::VarIntOverflow)
+        ));
+        assert!(matches!(
+            read_uleb("ffffffffffffffffff8100"),
+            Err(BufferError::VarIntOverflow)
+        ));
+        assert!(matches!(read_uleb("80"), Err(BufferError::OutOfBounds)));
+
+        assert!(matches!(
+            read_sleb("8000"),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+        assert!(matches!(
+            read_sleb("ff7f"),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+        assert!(matches!(
+            read_sleb("80808080808080808040"),
+            Err(BufferError::VarIntOverflow)
+        ));
+        assert!(matches!(
+            read_sleb("ffffffffffffffffff01"),
+            Err(BufferError::VarIntOverflow)
+        ));
+        assert!(matches!(
+            read_sleb("ffffffffffffffffff8000"),
+            Err(BufferError::VarIntOverflow)
+        ));
+
+        let mut buffer = Buffer::from(hex::decode("8000").unwrap());
+        assert!(buffer.read_zigzag().is_err());
+    }
+
     // #[test]
     // fn test_write_hash() {
     //     let hash = Hash::from("bb42edce1895f9a969e81d7371ec113a0966e5d55035a84f87ca098e4f0a1a86");
@@ -598,6 +1272,7 @@ mod tests {
     //     buffer.write_hash(hash);
     // }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_to_hex() {
         let version: u32 = 123456789;
@@ -628,6 +1303,22 @@ mod tests {
         assert_eq!(hex, "15cd5b07")
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io() {
+        use std::io::{Read, Write};
+
+        let mut buffer = Buffer::new();
+        buffer.write_all(&[1, 2, 3]).unwrap();
+        buffer.read_u8().unwrap();
+
+        let mut rest = Vec::new();
+        buffer.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, vec![2, 3]);
+        assert_eq!(buffer.read(&mut [0; 4]).unwrap(), 0);
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn test_from_hex() {
         //@todo would really like to support this type of from/toHx.
@@ -638,6 +1329,44 @@ mod tests {
         assert_eq!(buffer, Buffer::from(vec![255, 0]));
     }
 
+    #[test]
+    fn test_base58check() {
+        let buffer =
+            Buffer::from(hex::decode("00010966776006953d5567439e5e39f86a0d273bee").unwrap());
+
+        assert_eq!(buffer.to_base58check(), "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM");
+        assert_eq!(
+            Buffer::from_base58check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM").unwrap(),
+            buffer
+        );
+        assert_eq!(Buffer::from_base58(&buffer.to_base58()).unwrap(), buffer);
+    }
+
+    #[test]
+    fn test_bech32() {
+        let buffer = Buffer::from(hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+
+        let encoded = buffer.to_bech32("hs", Variant::Bech32m).unwrap();
+        let (hrp, decoded, variant) = Buffer::from_bech32(&encoded).unwrap();
+
+        assert_eq!(hrp, "hs");
+        assert_eq!(decoded, buffer);
+        assert_eq!(variant, Variant::Bech32m);
+    }
+
+    #[test]
+    fn test_base64_base32() {
+        let buffer = Buffer::from(b"foobar".to_vec());
+
+        assert_eq!(buffer.to_base64(), "Zm9vYmFy");
+        assert_eq!(buffer.to_base64_url(), "Zm9vYmFy");
+        assert_eq!(buffer.to_base32(), "mzxw6ytboi");
+        assert_eq!(Buffer::from_base64("Zm9vYmFy").unwrap(), buffer);
+        assert_eq!(Buffer::from_base64_url("Zm9vYmFy").unwrap(), buffer);
+        assert_eq!(Buffer::from_base32("MZXW6YTBOI======").unwrap(), buffer);
+        assert_eq!(Buffer::from_zbase32(&buffer.to_zbase32()).unwrap(), buffer);
+    }
+
     #[cfg(feature = "serialization")]
     #[test]
     fn test_serde() {
@@ -649,11 +1378,660 @@ mod tests {
 
         buffer.write_u32(version);
 
-        static version_bytes: [u8; 4] = [21, 205, 91, 7];
+        static VERSION_BYTES: [u8; 4] = [21, 205, 91, 7];
 
         let buffer_readable = buffer.clone();
 
-        assert_tokens(&buffer.compact(), &[Token::BorrowedBytes(&version_bytes)]);
+        assert_tokens(&buffer.compact(), &[Token::BorrowedBytes(&VERSION_BYTES)]);
         assert_tokens(&buffer_readable.readable(), &[Token::Str("15cd5b07")]);
     }
+
+    #[test]
+    fn test_offsets() {
+        let mut buffer = Buffer::from(hex::decode("0102030405").unwrap());
+        assert_eq!(buffer.peek_u8().unwrap(), 1);
+        assert_eq!(buffer.peek_u16_be().unwrap(), 0x0102);
+        assert_eq!(buffer.offset(), 0);
+
+        buffer.seek(3).unwrap();
+        assert_eq!(buffer.remaining(), 2);
+        assert_eq!(buffer.peek_bytes(2).unwrap(), &[4, 5]);
+        assert!(buffer.peek_u32().is_err());
+        assert_eq!(buffer.offset(), 3);
+
+        //Peeking only borrows the buffer.
+        let shared = &buffer;
+        assert_eq!(shared.peek_u16().unwrap(), 0x0504);
+        assert!(matches!(
+            Buffer::from(hex::decode("fd0100").unwrap()).peek_varint(),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+
+        buffer.seek_back(1).unwrap();
+        assert_eq!(buffer.read_u8().unwrap(), 3);
+        assert!(buffer.seek_back(5).is_err());
+        assert_eq!(buffer.offset(), 3);
+
+        buffer.set_offset(5).unwrap();
+        assert_eq!(buffer.remaining(), 0);
+        assert!(buffer.set_offset(6).is_err());
+
+        buffer.rewind();
+        assert_eq!(buffer.read_u32().unwrap(), 0x04030201);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut buffer = Buffer::from(hex::decode("01020304").unwrap());
+
+        {
+            let mut attempt = buffer.checkpoint();
+            assert_eq!(attempt.read_u16().unwrap(), 0x0201);
+            assert!(attempt.read_u32().is_err());
+        }
+        assert_eq!(buffer.offset(), 0);
+
+        let mut attempt = buffer.checkpoint();
+        assert_eq!(attempt.read_u8().unwrap(), 1);
+        attempt.commit();
+        assert_eq!(buffer.offset(), 1);
+    }
+
+    #[test]
+    fn test_sub_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.write_var_bytes(&[1, 2, 3]);
+        buffer.write_u8(4);
+        assert!(Buffer::from(hex::decode("0501").unwrap())
+            .read_var_sub_buffer()
+            .is_err());
+
+        let mut sub = buffer.read_var_sub_buffer().unwrap();
+        assert_eq!(sub.read_u16().unwrap(), 0x0201);
+        assert!(matches!(
+            sub.expect_consumed(),
+            Err(BufferError::TrailingBytes(1))
+        ));
+        assert!(sub.read_u16().is_err());
+        assert_eq!(sub.read_u8().unwrap(), 3);
+        sub.expect_consumed().unwrap();
+
+        assert!(buffer.read_sub_buffer(2).is_err());
+        let mut sub = buffer.read_sub_buffer(1).unwrap();
+        assert_eq!(sub.read_u8().unwrap(), 4);
+        buffer.expect_consumed().unwrap();
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut buffer = Buffer::new();
+        let size = buffer.reserve_u32();
+        let count = buffer.reserve_varint();
+        buffer.fill(0xAA, 0xFD);
+        buffer.patch(count, 0xFD).unwrap();
+        buffer.patch(size, buffer.len() - 4).unwrap();
+
+        assert_eq!(buffer.read_u32().unwrap(), 0x100);
+        assert_eq!(buffer.read_varint().unwrap(), VarInt::from(0xFDu64));
+        assert_eq!(buffer.remaining(), 0xFD);
+
+        let mut buffer = Buffer::new();
+        let size = buffer.reserve_u32();
+        assert!(matches!(
+            buffer.patch(size, usize::MAX),
+            Err(BufferError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_patch_order() {
+        //Patching the outer varint first would move the inner one.
+        let mut buffer = Buffer::new();
+        let outer = buffer.reserve_varint();
+        buffer.write_u8(1);
+        let inner = buffer.reserve_varint();
+        buffer.write_u8(2);
+        assert!(matches!(
+            buffer.patch(outer, 2),
+            Err(BufferError::StalePlaceholder)
+        ));
+        buffer.patch(inner, 1).unwrap();
+        assert_eq!(hex::encode(&buffer), "010102");
+
+        let mut buffer = Buffer::new();
+        let outer = buffer.reserve_varint();
+        buffer.write_u8(1);
+        let inner = buffer.reserve_varint();
+        buffer.write_u8(2);
+        buffer.patch(inner, 1).unwrap();
+        buffer.patch(outer, 3).unwrap();
+        assert_eq!(hex::encode(&buffer), "03010102");
+
+        //A multi-byte varint moves the placeholder reserved after it.
+        let mut buffer = Buffer::new();
+        let count = buffer.reserve_varint();
+        let size = buffer.reserve_u32();
+        buffer.patch(count, 0xFD).unwrap();
+        assert!(matches!(
+            buffer.patch(size, 1),
+            Err(BufferError::StalePlaceholder)
+        ));
+        assert_eq!(hex::encode(&buffer), "fdfd0000000000");
+    }
+
+    #[test]
+    fn test_length_prefixed() {
+        let mut buffer = Buffer::new();
+        buffer.write_u8(0xFF);
+        let ret = buffer.write_length_prefixed(|b| {
+            b.write_u16(1);
+            b.write_length_prefixed(|b| b.write_u8(2)).unwrap();
+            7
+        });
+        assert_eq!(ret.unwrap(), 7);
+        assert_eq!(hex::encode(&buffer), "ff0401000102");
+
+        let mut buffer = Buffer::new();
+        buffer.write_length_prefixed(|_| ()).unwrap();
+        assert_eq!(hex::encode(&buffer), "00");
+
+        let mut buffer = Buffer::new();
+        assert!(matches!(
+            buffer.write_length_prefixed(|b| b.reserve_varint()),
+            Err(BufferError::StalePlaceholder)
+        ));
+
+        let mut buffer = Buffer::new();
+        buffer.write_u16(1);
+        assert!(matches!(
+            buffer.write_length_prefixed(|b| b.truncate(1)),
+            Err(BufferError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut buffer = Buffer::new();
+        buffer.write_u8(0xFF);
+        buffer.write_str("hello");
+        buffer.write_checksum(1..).unwrap();
+        assert_eq!(buffer.len(), 10);
+        //First 4 bytes of sha256d("hello").
+        assert_eq!(hex::encode(&buffer[6..]), "9595c9df");
+        assert!(buffer.write_checksum(..20).is_err());
+
+        buffer.seek(6).unwrap();
+        buffer.read_checksum(1..6).unwrap();
+
+        buffer.seek_back(4).unwrap();
+        assert!(matches!(
+            buffer.read_checksum(0..6),
+            Err(BufferError::InvalidChecksum)
+        ));
+    }
+
+    #[test]
+    fn test_u128() {
+        let mut buffer = Buffer::new();
+        buffer.write_u128(1);
+        buffer.write_u128_be(1);
+        buffer.write_i128(-2);
+        buffer.write_i128_be(i128::MIN);
+
+        assert_eq!(buffer[0], 1);
+        assert_eq!(buffer[31], 1);
+        assert_eq!(buffer.read_u128().unwrap(), 1);
+        assert_eq!(buffer.read_u128_be().unwrap(), 1);
+        assert_eq!(buffer.read_i128().unwrap(), -2);
+        assert_eq!(buffer.read_i128_be().unwrap(), i128::MIN);
+        assert!(buffer.read_u128().is_err());
+    }
+
+    #[test]
+    fn test_u24_u48() {
+        let mut buffer = Buffer::new();
+        buffer.write_u24(0x010203).unwrap();
+        buffer.write_u24_be(0x010203).unwrap();
+        buffer.write_u48(0x0102_0304_0506).unwrap();
+        buffer.write_u48_be(0xFFFF_FFFF_FFFF).unwrap();
+        assert_eq!(hex::encode(&buffer), "030201010203060504030201ffffffffffff");
+
+        assert!(matches!(
+            buffer.write_u24(0x1000000),
+            Err(BufferError::OutOfRange)
+        ));
+        assert!(matches!(
+            buffer.write_u48_be(1 << 48),
+            Err(BufferError::OutOfRange)
+        ));
+        assert_eq!(buffer.len(), 18);
+
+        assert_eq!(buffer.read_u24().unwrap(), 0x010203);
+        assert_eq!(buffer.read_u24_be().unwrap(), 0x010203);
+        assert_eq!(buffer.read_u48().unwrap(), 0x0102_0304_0506);
+        assert_eq!(buffer.read_u48_be().unwrap(), 0xFFFF_FFFF_FFFF);
+        assert!(buffer.read_u24().is_err());
+    }
+
+    #[test]
+    fn test_i24_i48() {
+        let mut buffer = Buffer::new();
+        buffer.write_i24(-1).unwrap();
+        buffer.write_i24_be(-0x800000).unwrap();
+        buffer.write_i48(0x7FFF_FFFF_FFFF).unwrap();
+        buffer.write_i48_be(-2).unwrap();
+        assert_eq!(hex::encode(&buffer), "ffffff800000ffffffffff7ffffffffffffe");
+
+        assert!(buffer.write_i24(0x800000).is_err());
+        assert!(buffer.write_i24_be(-0x800001).is_err());
+        assert!(buffer.write_i48(1 << 47).is_err());
+        assert!(buffer.write_i48_be(-(1 << 47) - 1).is_err());
+
+        assert_eq!(buffer.read_i24().unwrap(), -1);
+        assert_eq!(buffer.read_i24_be().unwrap(), -0x800000);
+        assert_eq!(buffer.read_i48().unwrap(), 0x7FFF_FFFF_FFFF);
+        assert_eq!(buffer.read_i48_be().unwrap(), -2);
+    }
+
+    #[test]
+    fn test_floats() {
+        let mut buffer = Buffer::new();
+        buffer.write_f32(1.5);
+        buffer.write_f32_be(-0.0);
+        buffer.write_f64(f64::INFINITY);
+        buffer.write_f64_be(0.1);
+        assert_eq!(
+            hex::encode(&buffer),
+            "0000c03f80000000000000000000f07f3fb999999999999a"
+        );
+
+        assert_eq!(buffer.read_f32().unwrap(), 1.5);
+        assert!(buffer.read_f32_be().unwrap().is_sign_negative());
+        assert_eq!(buffer.read_f64().unwrap(), f64::INFINITY);
+        assert_eq!(buffer.read_f64_be().unwrap(), 0.1);
+    }
+
+    #[test]
+    fn test_nan() {
+        let mut buffer = Buffer::new();
+        buffer.write_f32(f32::from_bits(0xFFC0_0001));
+        buffer.write_f64_be(-f64::NAN);
+        assert_eq!(hex::encode(&buffer), "0000c07f7ff8000000000000");
+        assert!(buffer.read_f32().unwrap().is_nan());
+        assert!(buffer.read_f64_be().unwrap().is_nan());
+
+        let mut buffer = Buffer::new();
+        buffer.write_u32(0x7FC0_0001);
+        buffer.write_u64(0xFFF8_0000_0000_0000);
+        assert!(matches!(
+            buffer.read_f32(),
+            Err(BufferError::NonCanonicalNaN)
+        ));
+        assert_eq!(buffer.offset(), 0);
+        buffer.seek(4).unwrap();
+        assert!(matches!(
+            buffer.read_f64(),
+            Err(BufferError::NonCanonicalNaN)
+        ));
+        assert_eq!(buffer.offset(), 4);
+    }
+
+    #[test]
+    fn test_bool() {
+        let mut buffer = Buffer::new();
+        buffer.write_bool(true);
+        buffer.write_bool(false);
+        buffer.write_u8(2);
+
+        assert!(buffer.read_bool().unwrap());
+        assert!(!buffer.read_bool().unwrap());
+        assert!(matches!(
+            buffer.read_bool(),
+            Err(BufferError::InvalidBool(2))
+        ));
+        assert_eq!(buffer.read_u8().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_var_string() {
+        let mut buffer = Buffer::new();
+        buffer.write_var_string("héllo");
+        buffer.write_var_string_with("hns", HandshakeName).unwrap();
+        assert!(matches!(
+            buffer.write_var_string_with("-hns", HandshakeName),
+            Err(BufferError::StringRejected)
+        ));
+        assert_eq!(hex::encode(&buffer), "0668c3a96c6c6f03686e73");
+
+        assert!(matches!(
+            buffer.read_var_string_with(Ascii),
+            Err(BufferError::StringRejected)
+        ));
+        assert_eq!(buffer.offset(), 0);
+        assert_eq!(buffer.read_var_string().unwrap(), "héllo");
+        assert!(buffer
+            .read_var_string_with((Printable, MaxLength(2)))
+            .is_err());
+        assert_eq!(
+            buffer
+                .read_var_string_with(|s: &str| s.starts_with('h'))
+                .unwrap(),
+            "hns"
+        );
+
+        let mut buffer = Buffer::from(hex::decode("05ff").unwrap());
+        assert!(matches!(
+            buffer.read_var_string(),
+            Err(BufferError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_validators() {
+        assert!(Printable.validate("Hello, World!"));
+        assert!(!Printable.validate("tab\t"));
+        assert!(Ascii.validate("tab\t"));
+        assert!(MaxLength(3).validate("abc"));
+        assert!(!MaxLength(3).validate("abcd"));
+
+        assert!(HandshakeName.validate("a"));
+        assert!(HandshakeName.validate("my-name_0"));
+        assert!(HandshakeName.validate(&"a".repeat(63)));
+        assert!(!HandshakeName.validate(&"a".repeat(64)));
+        assert!(!HandshakeName.validate(""));
+        assert!(!HandshakeName.validate("Upper"));
+        assert!(!HandshakeName.validate("name_"));
+        assert!(!HandshakeName.validate("dot.name"));
+    }
+
+    #[test]
+    fn test_cstring() {
+        let mut buffer = Buffer::new();
+        buffer.write_cstring("abc").unwrap();
+        buffer.write_cstring("").unwrap();
+        buffer.write_str("toolong");
+        buffer.write_u8(0);
+        buffer.write_str("end");
+        assert!(matches!(
+            buffer.write_cstring("a\0b"),
+            Err(BufferError::StringRejected)
+        ));
+
+        assert_eq!(buffer.read_cstring(3).unwrap(), "abc");
+        assert_eq!(buffer.read_cstring(0).unwrap(), "");
+        assert!(matches!(
+            buffer.read_cstring(6),
+            Err(BufferError::StringTooLong(6))
+        ));
+        assert_eq!(buffer.read_cstring(7).unwrap(), "toolong");
+        assert!(matches!(
+            buffer.read_cstring(10),
+            Err(BufferError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_vec() {
+        let mut buffer = Buffer::new();
+        buffer
+            .write_vec(&[1u16, 2, 3], |b, item| {
+                b.write_u16(*item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(hex::encode(&buffer), "03010002000300");
+
+        let items = buffer.read_vec(|b| b.read_u16()).unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+
+        //A huge count fails on the missing items instead of preallocating for it.
+        let mut buffer = Buffer::from(hex::decode("ffffffffffffffff7f00").unwrap());
+        assert!(matches!(
+            buffer.read_vec(|b| b.read_u8()),
+            Err(BufferError::OutOfBounds)
+        ));
+
+        //Items that take no bytes can outnumber the bytes left.
+        let mut buffer = Buffer::from(vec![0x03]);
+        assert_eq!(buffer.read_vec(|_| Ok(())).unwrap(), vec![(), (), ()]);
+        buffer.rewind();
+        assert_eq!(buffer.read_set(false, |_| Ok(())).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = BTreeMap::new();
+        map.insert(2u8, "b".to_string());
+        map.insert(1u8, "a".to_string());
+
+        let mut buffer = Buffer::new();
+        buffer
+            .write_map(&map, |b, key, value| {
+                b.write_u8(*key);
+                b.write_var_string(value);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(hex::encode(&buffer), "02010161020162");
+
+        let read = |b: &mut Buffer| Ok((b.read_u8()?, b.read_var_string()?));
+        assert_eq!(buffer.read_map(read).unwrap(), map);
+
+        let mut buffer = Buffer::from(hex::decode("02020162010161").unwrap());
+        assert!(matches!(
+            buffer.read_map(read),
+            Err(BufferError::UnsortedEntries)
+        ));
+
+        let mut buffer = Buffer::from(hex::decode("02010161010162").unwrap());
+        assert!(matches!(
+            buffer.read_map(read),
+            Err(BufferError::DuplicateEntry)
+        ));
+    }
+
+    #[test]
+    fn test_set() {
+        let set: BTreeSet<u8> = [3, 1].iter().cloned().collect();
+
+        let mut buffer = Buffer::new();
+        buffer
+            .write_set(&set, |b, item| {
+                b.write_u8(*item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(hex::encode(&buffer), "020103");
+        assert_eq!(buffer.read_set(true, |b| b.read_u8()).unwrap(), set);
+
+        let mut buffer = Buffer::from(hex::decode("03030101").unwrap());
+        assert!(matches!(
+            buffer.read_set(true, |b| b.read_u8()),
+            Err(BufferError::DuplicateEntry)
+        ));
+
+        buffer.rewind();
+        assert_eq!(buffer.read_set(false, |b| b.read_u8()).unwrap(), set);
+    }
+
+    #[test]
+    fn test_huge_lengths() {
+        let mut buffer = Buffer::from(hex::decode("ff0000000000000080aa").unwrap());
+        assert!(matches!(
+            buffer.read_var_bytes(),
+            Err(BufferError::OutOfBounds)
+        ));
+
+        let mut buffer = Buffer::from(hex::decode("aa").unwrap());
+        buffer.read_u8().unwrap();
+        assert!(buffer.read_bytes(usize::MAX).is_err());
+        assert!(buffer.seek(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_varint_boundaries() {
+        let vectors: &[(usize, &str)] = &[
+            (0xFC, "fc"),
+            (0xFD, "fdfd00"),
+            (0xFFFF, "fdffff"),
+            (0x10000, "fe00000100"),
+            (0xFFFFFFFF, "feffffffff"),
+            (0x100000000, "ff0000000001000000"),
+        ];
+
+        for (num, hex) in vectors {
+            let mut buffer = Buffer::new();
+            buffer.write_varint(*num);
+            assert_eq!(hex::encode(&buffer), *hex);
+            assert_eq!(buffer.read_varint().unwrap(), VarInt::from(*num));
+        }
+    }
+
+    //Every boundary where one of the encodings changes length, and the values either side of it.
+    fn boundary() -> impl Strategy<Value = u64> {
+        let mut edges = vec![
+            0,
+            0xFC,
+            0xFD,
+            u8::MAX as u64,
+            u16::MAX as u64,
+            u32::MAX as u64,
+            i64::MAX as u64,
+            u64::MAX,
+        ];
+        //LEB128 and Core's VARINT change length every 7 bits.
+        edges.extend((1..10).map(|i| 1u64 << (7 * i)));
+        edges.extend((1..10).map(|i| 1u64 << (7 * i - 1)));
+
+        (prop::sample::select(edges), -2i64..=2)
+            .prop_map(|(edge, delta)| edge.wrapping_add(delta as u64))
+    }
+
+    fn any_u64() -> impl Strategy<Value = u64> {
+        prop_oneof![boundary(), any::<u64>()]
+    }
+
+    fn any_i64() -> impl Strategy<Value = i64> {
+        prop_oneof![
+            boundary().prop_map(|n| n as i64),
+            boundary().prop_map(|n| (n as i64).wrapping_neg()),
+            any::<i64>()
+        ]
+    }
+
+    fn any_i128() -> impl Strategy<Value = i128> {
+        prop_oneof![
+            any_i64().prop_map(|n| n as i128),
+            (any_i64(), any::<u64>()).prop_map(|(hi, lo)| (hi as i128) << 64 | lo as i128),
+            Just(i128::MIN),
+            Just(i128::MAX),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_varint_round_trip(num in any_u64()) {
+            let varint = VarInt::from(num);
+            let mut buffer = Buffer::new();
+            buffer.write_varint(num as usize);
+
+            let (bytes, len) = varint.encode();
+            prop_assert_eq!(&buffer[..], &bytes[..len]);
+            prop_assert_eq!(varint.encoded_size() as usize, len);
+            prop_assert_eq!(buffer.read_varint().unwrap(), varint);
+            prop_assert!(buffer.read_u8().is_err());
+        }
+
+        #[test]
+        fn prop_varint2_round_trip(num in any_u64()) {
+            let mut buffer = Buffer::new();
+            buffer.write_varint2(num);
+
+            prop_assert_eq!(CoreVarInt::from(num).encoded_size() as usize, buffer.len());
+            prop_assert_eq!(buffer.read_varint2().unwrap().to_u64(), num);
+            prop_assert!(buffer.read_u8().is_err());
+        }
+
+        #[test]
+        fn prop_leb128_round_trip(num in any_u64(), signed in any_i64(), wide in any_i128()) {
+            let mut buffer = Buffer::new();
+            buffer.write_uleb128(num);
+            buffer.write_sleb128(signed);
+            buffer.write_zigzag(signed);
+            buffer.write_uleb128_u128(wide as u128);
+            buffer.write_sleb128_i128(wide);
+            buffer.write_zigzag_i128(wide);
+
+            prop_assert_eq!(buffer.read_uleb128().unwrap(), num);
+            prop_assert_eq!(buffer.read_sleb128().unwrap(), signed);
+            prop_assert_eq!(buffer.read_zigzag().unwrap(), signed);
+            prop_assert_eq!(buffer.read_uleb128_u128().unwrap(), wide as u128);
+            prop_assert_eq!(buffer.read_sleb128_i128().unwrap(), wide);
+            prop_assert_eq!(buffer.read_zigzag_i128().unwrap(), wide);
+            prop_assert!(buffer.read_u8().is_err());
+        }
+
+        //Any bytes that decode have to be exactly what the writer produces for that value, so no
+        //number has a second encoding.
+        #[test]
+        fn prop_varint_minimal(bytes in prop::collection::vec(any::<u8>(), 1..20)) {
+            let mut buffer = Buffer::from(bytes.clone());
+            if let Ok(num) = buffer.read_varint() {
+                let mut expected = Buffer::new();
+                expected.write_varint(num.to_u64() as usize);
+                prop_assert_eq!(&bytes[..expected.len()], &expected[..]);
+            }
+
+            let mut buffer = Buffer::from(bytes.clone());
+            if let Ok(num) = buffer.read_varint2() {
+                let mut expected = Buffer::new();
+                expected.write_varint2(num.to_u64());
+                prop_assert_eq!(&bytes[..expected.len()], &expected[..]);
+            }
+
+            let mut buffer = Buffer::from(bytes.clone());
+            if let Ok(num) = buffer.read_uleb128() {
+                let mut expected = Buffer::new();
+                expected.write_uleb128(num);
+                prop_assert_eq!(&bytes[..expected.len()], &expected[..]);
+            }
+
+            let mut buffer = Buffer::from(bytes.clone());
+            if let Ok(num) = buffer.read_sleb128() {
+                let mut expected = Buffer::new();
+                expected.write_sleb128(num);
+                prop_assert_eq!(&bytes[..expected.len()], &expected[..]);
+            }
+
+            let mut buffer = Buffer::from(bytes.clone());
+            if let Ok(num) = buffer.read_sleb128_i128() {
+                let mut expected = Buffer::new();
+                expected.write_sleb128_i128(num);
+                prop_assert_eq!(&bytes[..expected.len()], &expected[..]);
+            }
+        }
+
+        //Non-minimal CompactSize is rejected for every value that fits in a shorter prefix.
+        #[test]
+        fn prop_varint_rejects_non_minimal(num in any_u64()) {
+            let short = VarInt::from(num).encoded_size();
+            let mut encodings = Vec::new();
+            if short < 3 {
+                encodings.push([&[0xFD][..], &(num as u16).to_le_bytes()].concat());
+            }
+            if short < 5 {
+                encodings.push([&[0xFE][..], &(num as u32).to_le_bytes()].concat());
+            }
+            if short < 9 {
+                encodings.push([&[0xFF][..], &num.to_le_bytes()].concat());
+            }
+
+            for encoded in encodings {
+                prop_assert!(matches!(
+                    VarInt::decode(&encoded),
+                    Err(BufferError::NonMinimalVarInt)
+                ));
+            }
+        }
+    }
 }
@@ -0,0 +1,157 @@
+//! Base32 (RFC 4648) and z-base-32.
+//!
+//! Both are written lowercase and without padding, which keeps them usable as DNS labels and
+//! matches the `bs32` encoding Handshake uses. Decoding is case-insensitive and accepts padding,
+//! but rejects non-zero trailing bits.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+const RFC4648: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const ZBASE32: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Base32Error {
+    InvalidCharacter { c: char, index: usize },
+    InvalidLength(usize),
+    InvalidPadding,
+}
+
+impl fmt::Display for Base32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base32Error::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            Base32Error::InvalidLength(len) => write!(f, "Invalid Length: {len}"),
+            Base32Error::InvalidPadding => write!(f, "Invalid Padding"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Base32Error {}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, RFC4648)
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, Base32Error> {
+    decode_with(encoded, RFC4648)
+}
+
+pub fn encode_zbase32(data: &[u8]) -> String {
+    encode_with(data, ZBASE32)
+}
+
+pub fn decode_zbase32(encoded: &str) -> Result<Vec<u8>, Base32Error> {
+    decode_with(encoded, ZBASE32)
+}
+
+fn encode_with(data: &[u8], alphabet: &[u8; 32]) -> String {
+    let mut encoded = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(alphabet[(acc >> bits) as usize & 31] as char);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        encoded.push(alphabet[(acc << (5 - bits)) as usize & 31] as char);
+    }
+    encoded
+}
+
+fn decode_with(encoded: &str, alphabet: &[u8; 32]) -> Result<Vec<u8>, Base32Error> {
+    let unpadded = encoded.trim_end_matches('=');
+    //Padding, if any, has to fill out exactly the last group of 8.
+    if unpadded.len() != encoded.len() && (unpadded.len() + 7) / 8 * 8 != encoded.len() {
+        return Err(Base32Error::InvalidPadding);
+    }
+    if let 1 | 3 | 6 = unpadded.len() % 8 {
+        return Err(Base32Error::InvalidLength(unpadded.len()));
+    }
+
+    let mut decoded = Vec::with_capacity(unpadded.len() * 5 / 8);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for (index, c) in unpadded.chars().enumerate() {
+        let lower = c.to_ascii_lowercase();
+        let value = alphabet
+            .iter()
+            .position(|digit| lower.is_ascii() && *digit == lower as u8)
+            .ok_or(Base32Error::InvalidCharacter { c, index })?;
+
+        acc = (acc << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    if acc != 0 {
+        return Err(Base32Error::InvalidPadding);
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+
+        for (data, padded) in vectors.iter() {
+            let encoded = padded.trim_end_matches('=').to_lowercase();
+
+            assert_eq!(encode(data.as_bytes()), encoded);
+            assert_eq!(decode(&encoded).unwrap(), data.as_bytes());
+            assert_eq!(decode(padded).unwrap(), data.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_zbase32() {
+        assert_eq!(encode_zbase32(&[0xf0, 0xbf, 0xc7]), "6n9hq");
+        assert_eq!(encode_zbase32(&[0xd4, 0x7a, 0x04]), "4t7ye");
+        assert_eq!(decode_zbase32("6n9hq").unwrap(), [0xf0, 0xbf, 0xc7]);
+        assert_eq!(decode_zbase32("4T7YE").unwrap(), [0xd4, 0x7a, 0x04]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode("my="), Err(Base32Error::InvalidPadding));
+        assert_eq!(decode("my======").unwrap(), b"f");
+        assert_eq!(decode("my=============="), Err(Base32Error::InvalidPadding));
+        assert_eq!(decode("========"), Err(Base32Error::InvalidPadding));
+        assert_eq!(decode("mz"), Err(Base32Error::InvalidPadding));
+        assert_eq!(decode("mzx"), Err(Base32Error::InvalidLength(3)));
+        assert_eq!(
+            decode("m1"),
+            Err(Base32Error::InvalidCharacter { c: '1', index: 1 })
+        );
+        assert_eq!(
+            decode_zbase32("6n9hv"),
+            Err(Base32Error::InvalidCharacter { c: 'v', index: 4 })
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Base58 and Base58Check using the Bitcoin alphabet.
+
+use crate::hash::{HashEngine, Sha256d};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Base58Error {
+    InvalidCharacter { c: char, index: usize },
+    InvalidChecksum { expected: [u8; 4], got: [u8; 4] },
+    TooShort(usize),
+}
+
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            Base58Error::InvalidChecksum { expected, got } => write!(
+                f,
+                "Invalid Checksum: expected {expected:02x?}, got {got:02x?}"
+            ),
+            Base58Error::TooShort(len) => write!(f, "Too Short: {len} bytes can't hold a checksum"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Base58Error {}
+
+pub fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|byte| **byte == 0).count();
+
+    //Base58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for byte in &data[zeros..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut encoded = String::with_capacity(zeros + digits.len());
+    encoded.extend(core::iter::repeat('1').take(zeros));
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|digit| ALPHABET[*digit as usize] as char),
+    );
+    encoded
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, Base58Error> {
+    let zeros = encoded.chars().take_while(|c| *c == '1').count();
+
+    //Bytes, least significant first.
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len() * 733 / 1000 + 1);
+    for (index, c) in encoded.chars().enumerate().skip(zeros) {
+        let mut carry = digit_value(c).ok_or(Base58Error::InvalidCharacter { c, index })? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes.iter().rev());
+    Ok(decoded)
+}
+
+/// Base58 of `data` followed by the first 4 bytes of its double SHA-256.
+pub fn encode_check(data: &[u8]) -> String {
+    let mut payload = Vec::with_capacity(data.len() + 4);
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(&checksum(data));
+    encode(&payload)
+}
+
+/// Decodes Base58Check, verifies the checksum and returns the data without it.
+pub fn decode_check(encoded: &str) -> Result<Vec<u8>, Base58Error> {
+    let mut decoded = decode(encoded)?;
+    if decoded.len() < 4 {
+        return Err(Base58Error::TooShort(decoded.len()));
+    }
+
+    let split = decoded.len() - 4;
+    let mut got = [0; 4];
+    got.copy_from_slice(&decoded[split..]);
+    let expected = checksum(&decoded[..split]);
+    if expected != got {
+        return Err(Base58Error::InvalidChecksum { expected, got });
+    }
+
+    decoded.truncate(split);
+    Ok(decoded)
+}
+
+//First 4 bytes of the double SHA-256, also used by Buffer::write_checksum.
+pub(crate) fn checksum(data: &[u8]) -> [u8; 4] {
+    let mut engine = Sha256d::default();
+    engine.input(data);
+    let hash = engine.finalize();
+
+    let mut checksum = [0; 4];
+    checksum.copy_from_slice(&hash.as_ref()[..4]);
+    checksum
+}
+
+fn digit_value(c: char) -> Option<u8> {
+    if !c.is_ascii() {
+        return None;
+    }
+    ALPHABET
+        .iter()
+        .position(|digit| *digit == c as u8)
+        .map(|value| value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_vectors() {
+        let vectors: &[(&str, &str)] = &[
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            ("572e4794", "3EFU7m"),
+            ("10c8511e", "Rt5zm"),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("00000000000000000000", "1111111111"),
+            ("ffffffffffffffffffff", "FPBt6CHo3fovdL"),
+            (
+                "73696d706c792061206c6f6e6720737472696e67",
+                "2cFupjhnEsSn59qHXstmK2ffpLv2",
+            ),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+        ];
+
+        for (data, encoded) in vectors {
+            let data = hex::decode(data).unwrap();
+            assert_eq!(encode(&data), *encoded);
+            assert_eq!(decode(encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        //P2PKH address of hash160 010966776006953d5567439e5e39f86a0d273bee.
+        let payload = hex::decode("00010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        let address = "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM";
+
+        assert_eq!(encode_check(&payload), address);
+        assert_eq!(decode_check(address).unwrap(), payload);
+
+        assert!(matches!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN"),
+            Err(Base58Error::InvalidChecksum { .. })
+        ));
+        assert_eq!(decode_check("111"), Err(Base58Error::TooShort(3)));
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            Base58Error::InvalidCharacter { c: '0', index: 4 }.to_string(),
+            "Invalid character '0' at position 4"
+        );
+        assert_eq!(
+            Base58Error::TooShort(3).to_string(),
+            "Too Short: 3 bytes can't hold a checksum"
+        );
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(
+            decode("16UwLL0Risc"),
+            Err(Base58Error::InvalidCharacter { c: '0', index: 6 })
+        );
+        assert_eq!(
+            decode("1lI"),
+            Err(Base58Error::InvalidCharacter { c: 'l', index: 1 })
+        );
+        assert_eq!(
+            decode("2gé"),
+            Err(Base58Error::InvalidCharacter { c: 'é', index: 2 })
+        );
+    }
+}
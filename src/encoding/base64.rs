@@ -0,0 +1,159 @@
+//! Base64 (RFC 4648), standard and URL-safe.
+//!
+//! [`encode`] pads its output, [`encode_url`] doesn't. Both decoders accept input with or without
+//! padding, but reject padding in the wrong place and non-zero trailing bits, so every payload
+//! has exactly two accepted encodings per alphabet, the padded one and the unpadded one, which
+//! are the same when the payload is a multiple of 3 bytes.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Base64Error {
+    InvalidCharacter { c: char, index: usize },
+    InvalidLength(usize),
+    InvalidPadding,
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base64Error::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            Base64Error::InvalidLength(len) => write!(f, "Invalid Length: {len}"),
+            Base64Error::InvalidPadding => write!(f, "Invalid Padding"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Base64Error {}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, STANDARD, true)
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, Base64Error> {
+    decode_with(encoded, STANDARD)
+}
+
+pub fn encode_url(data: &[u8]) -> String {
+    encode_with(data, URL_SAFE, false)
+}
+
+pub fn decode_url(encoded: &str) -> Result<Vec<u8>, Base64Error> {
+    decode_with(encoded, URL_SAFE)
+}
+
+fn encode_with(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(alphabet[(bits >> (18 - 6 * i)) as usize & 63] as char);
+            } else if pad {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_with(encoded: &str, alphabet: &[u8; 64]) -> Result<Vec<u8>, Base64Error> {
+    let unpadded = encoded.trim_end_matches('=');
+    let padding = encoded.len() - unpadded.len();
+    if padding > 0 && (padding > 2 || encoded.len() % 4 != 0) {
+        return Err(Base64Error::InvalidPadding);
+    }
+    if unpadded.len() % 4 == 1 {
+        return Err(Base64Error::InvalidLength(unpadded.len()));
+    }
+
+    let mut decoded = Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for (index, c) in unpadded.chars().enumerate() {
+        let value = alphabet
+            .iter()
+            .position(|digit| c.is_ascii() && *digit == c as u8)
+            .ok_or(Base64Error::InvalidCharacter { c, index })?;
+
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    if acc != 0 {
+        return Err(Base64Error::InvalidPadding);
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (data, encoded) in vectors.iter() {
+            assert_eq!(encode(data.as_bytes()), *encoded);
+            assert_eq!(decode(encoded).unwrap(), data.as_bytes());
+            assert_eq!(
+                decode(encoded.trim_end_matches('=')).unwrap(),
+                data.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_url_safe() {
+        let data = [0xfb, 0xff, 0xbf];
+
+        assert_eq!(encode(&data), "+/+/");
+        assert_eq!(encode_url(&data), "-_-_");
+        assert_eq!(encode_url(b"f"), "Zg");
+        assert_eq!(decode_url("-_-_").unwrap(), data);
+        assert_eq!(decode_url("Zg==").unwrap(), b"f");
+        assert_eq!(
+            decode_url("+/+/"),
+            Err(Base64Error::InvalidCharacter { c: '+', index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode("Zg").unwrap(), decode("Zg==").unwrap());
+        assert_eq!(decode("Zg="), Err(Base64Error::InvalidPadding));
+        assert_eq!(decode("Zm9v===="), Err(Base64Error::InvalidPadding));
+        assert_eq!(decode("Zh=="), Err(Base64Error::InvalidPadding));
+        assert_eq!(decode("Zm9vY"), Err(Base64Error::InvalidLength(5)));
+        assert_eq!(
+            decode("Zm=v"),
+            Err(Base64Error::InvalidCharacter { c: '=', index: 2 })
+        );
+    }
+}
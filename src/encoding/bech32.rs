@@ -0,0 +1,591 @@
+//! Bech32 (BIP173) and Bech32m (BIP350).
+//!
+//! [`encode`] and [`decode`] work on 5-bit values, use [`convert_bits`] to regroup bytes. The
+//! address helpers do that for you: [`encode_segwit`]/[`decode_segwit`] follow BIP350, picking
+//! the variant from the witness version, while [`encode_address`]/[`decode_address`] leave the
+//! variant to the caller, which is what Handshake's `hs1...` addresses need as they use Bech32
+//! for every version.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATORS: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const MAX_LENGTH: usize = 90;
+const MAX_HRP_LENGTH: usize = 83;
+const CHECKSUM_LENGTH: usize = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// BIP173, used for witness version 0 and by Handshake.
+    Bech32,
+    /// BIP350, used for witness versions 1 through 16.
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+
+    fn from_constant(constant: u32) -> Option<Variant> {
+        match constant {
+            1 => Some(Variant::Bech32),
+            0x2bc830a3 => Some(Variant::Bech32m),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Bech32Error {
+    InvalidCharacter { c: char, index: usize },
+    MixedCase(usize),
+    MissingSeparator,
+    InvalidLength(usize),
+    InvalidChecksum,
+    InvalidData { value: u8, from: u32, index: usize },
+    InvalidPadding,
+    InvalidHrp { expected: String, got: String },
+    InvalidWitnessVersion(u8),
+    InvalidProgramLength(usize),
+    InvalidVariant { expected: Variant, got: Variant },
+    InvalidBits { from: u32, to: u32 },
+}
+
+impl fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bech32Error::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            Bech32Error::MixedCase(index) => write!(f, "Mixed Case at position {index}"),
+            Bech32Error::MissingSeparator => write!(f, "Missing Separator"),
+            Bech32Error::InvalidLength(len) => write!(f, "Invalid Length: {len}"),
+            Bech32Error::InvalidChecksum => write!(f, "Invalid Checksum"),
+            Bech32Error::InvalidData { value, from, index } => {
+                write!(f, "Invalid {from}-bit value {value} at position {index}")
+            }
+            Bech32Error::InvalidPadding => write!(f, "Invalid Padding"),
+            Bech32Error::InvalidHrp { expected, got } => write!(
+                f,
+                "Invalid Human Readable Part: expected {expected}, got {got}"
+            ),
+            Bech32Error::InvalidWitnessVersion(version) => {
+                write!(f, "Invalid Witness Version: {version}")
+            }
+            Bech32Error::InvalidProgramLength(len) => write!(f, "Invalid Program Length: {len}"),
+            Bech32Error::InvalidVariant { expected, got } => {
+                write!(f, "Invalid Variant: expected {expected:?}, got {got:?}")
+            }
+            Bech32Error::InvalidBits { from, to } => {
+                write!(
+                    f,
+                    "Invalid Bits: can't regroup {from}-bit values to {to}-bit"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Bech32Error {}
+
+pub type Result<T> = core::result::Result<T, Bech32Error>;
+
+/// Encodes 5-bit `data` under `hrp`. The output is lowercase.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String> {
+    let hrp = check_hrp(hrp)?;
+    if let Some(index) = data.iter().position(|value| *value > 31) {
+        return Err(Bech32Error::InvalidData {
+            value: data[index],
+            from: 5,
+            index,
+        });
+    }
+
+    let length = hrp.len() + 1 + data.len() + CHECKSUM_LENGTH;
+    if length > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength(length));
+    }
+
+    let mut values = hrp_expand(hrp.as_bytes());
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0; CHECKSUM_LENGTH]);
+    let checksum = polymod(&values) ^ variant.constant();
+
+    let mut encoded = String::with_capacity(length);
+    encoded.push_str(&hrp);
+    encoded.push('1');
+    for value in data {
+        encoded.push(CHARSET[*value as usize] as char);
+    }
+    for i in 0..CHECKSUM_LENGTH {
+        let value = (checksum >> (5 * (5 - i))) & 31;
+        encoded.push(CHARSET[value as usize] as char);
+    }
+
+    Ok(encoded)
+}
+
+/// Decodes a Bech32 or Bech32m string, returning the lowercase hrp, the 5-bit data (without the
+/// checksum) and which variant the checksum matched.
+pub fn decode(encoded: &str) -> Result<(String, Vec<u8>, Variant)> {
+    let mut has_lower = false;
+    let mut has_upper = false;
+    for (index, c) in encoded.chars().enumerate() {
+        if !(33..=126).contains(&(c as u32)) {
+            return Err(Bech32Error::InvalidCharacter { c, index });
+        }
+        has_lower |= c.is_ascii_lowercase();
+        has_upper |= c.is_ascii_uppercase();
+        if has_lower && has_upper {
+            return Err(Bech32Error::MixedCase(index));
+        }
+    }
+
+    //Every character is ASCII from here on, so byte positions are character positions.
+    if encoded.len() < 2 + CHECKSUM_LENGTH || encoded.len() > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength(encoded.len()));
+    }
+
+    let separator = encoded.rfind('1').ok_or(Bech32Error::MissingSeparator)?;
+    if separator == 0 || separator > MAX_HRP_LENGTH {
+        return Err(Bech32Error::InvalidLength(separator));
+    }
+    if encoded.len() - separator - 1 < CHECKSUM_LENGTH {
+        return Err(Bech32Error::InvalidLength(encoded.len() - separator - 1));
+    }
+
+    let hrp = encoded[..separator].to_ascii_lowercase();
+    let mut data = Vec::with_capacity(encoded.len() - separator - 1);
+    for (index, c) in encoded.char_indices().skip(separator + 1) {
+        let value = CHARSET
+            .iter()
+            .position(|digit| *digit == c.to_ascii_lowercase() as u8)
+            .ok_or(Bech32Error::InvalidCharacter { c, index })?;
+        data.push(value as u8);
+    }
+
+    let mut values = hrp_expand(hrp.as_bytes());
+    values.extend_from_slice(&data);
+    let variant = Variant::from_constant(polymod(&values)).ok_or(Bech32Error::InvalidChecksum)?;
+
+    data.truncate(data.len() - CHECKSUM_LENGTH);
+    Ok((hrp, data, variant))
+}
+
+/// Regroups `data` from `from`-bit values to `to`-bit values, e.g. bytes to the 5-bit values
+/// Bech32 encodes. With `pad` the last group is zero-padded, without it leftover bits must be
+/// zero padding of less than `from` bits. Both widths have to be from 1 to 8.
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>> {
+    if !(1..=8).contains(&from) || !(1..=8).contains(&to) {
+        return Err(Bech32Error::InvalidBits { from, to });
+    }
+
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max: u32 = (1 << to) - 1;
+    let max_acc: u32 = (1 << (from + to - 1)) - 1;
+    let mut ret = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for (index, value) in data.iter().enumerate() {
+        if (*value as u32) >> from != 0 {
+            return Err(Bech32Error::InvalidData {
+                value: *value,
+                from,
+                index,
+            });
+        }
+        acc = ((acc << from) | *value as u32) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            ret.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            ret.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        return Err(Bech32Error::InvalidPadding);
+    }
+
+    Ok(ret)
+}
+
+/// Encodes a witness program with the variant BIP350 assigns to `version`.
+pub fn encode_segwit(hrp: &str, version: u8, program: &[u8]) -> Result<String> {
+    if version > 16 {
+        return Err(Bech32Error::InvalidWitnessVersion(version));
+    }
+    encode_address(hrp, version, program, segwit_variant(version))
+}
+
+/// Decodes a witness program, checking the hrp and that the variant matches the version as
+/// BIP350 requires.
+pub fn decode_segwit(hrp: &str, address: &str) -> Result<(u8, Vec<u8>)> {
+    let (version, program, variant) = decode_address(hrp, address)?;
+    if version > 16 {
+        return Err(Bech32Error::InvalidWitnessVersion(version));
+    }
+
+    let expected = segwit_variant(version);
+    if variant != expected {
+        return Err(Bech32Error::InvalidVariant {
+            expected,
+            got: variant,
+        });
+    }
+
+    Ok((version, program))
+}
+
+/// Encodes a version and program, e.g. a Handshake address, with an explicit variant.
+pub fn encode_address(hrp: &str, version: u8, program: &[u8], variant: Variant) -> Result<String> {
+    check_program(version, program.len())?;
+
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true)?);
+    encode(hrp, &data, variant)
+}
+
+/// Decodes a version and program under the expected `hrp`, whatever the variant.
+pub fn decode_address(hrp: &str, address: &str) -> Result<(u8, Vec<u8>, Variant)> {
+    let (got, data, variant) = decode(address)?;
+    if !got.eq_ignore_ascii_case(hrp) {
+        return Err(Bech32Error::InvalidHrp {
+            expected: hrp.to_ascii_lowercase(),
+            got,
+        });
+    }
+
+    let (version, data) = data
+        .split_first()
+        .ok_or(Bech32Error::InvalidProgramLength(0))?;
+    let program = convert_bits(data, 5, 8, false)?;
+    check_program(*version, program.len())?;
+
+    Ok((*version, program, variant))
+}
+
+fn segwit_variant(version: u8) -> Variant {
+    if version == 0 {
+        Variant::Bech32
+    } else {
+        Variant::Bech32m
+    }
+}
+
+fn check_program(version: u8, length: usize) -> Result<()> {
+    if version > 31 {
+        return Err(Bech32Error::InvalidWitnessVersion(version));
+    }
+    if !(2..=40).contains(&length) || (version == 0 && length != 20 && length != 32) {
+        return Err(Bech32Error::InvalidProgramLength(length));
+    }
+    Ok(())
+}
+
+fn check_hrp(hrp: &str) -> Result<String> {
+    if hrp.is_empty() || hrp.len() > MAX_HRP_LENGTH {
+        return Err(Bech32Error::InvalidLength(hrp.len()));
+    }
+
+    let mut has_lower = false;
+    let mut has_upper = false;
+    for (index, c) in hrp.chars().enumerate() {
+        if !(33..=126).contains(&(c as u32)) {
+            return Err(Bech32Error::InvalidCharacter { c, index });
+        }
+        has_lower |= c.is_ascii_lowercase();
+        has_upper |= c.is_ascii_uppercase();
+        if has_lower && has_upper {
+            return Err(Bech32Error::MixedCase(index));
+        }
+    }
+
+    Ok(hrp.to_ascii_lowercase())
+}
+
+fn hrp_expand(hrp: &[u8]) -> Vec<u8> {
+    let mut values = Vec::with_capacity(hrp.len() * 2 + 1 + MAX_LENGTH);
+    values.extend(hrp.iter().map(|c| c >> 5));
+    values.push(0);
+    values.extend(hrp.iter().map(|c| c & 31));
+    values
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ *value as u32;
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex;
+
+    //Witness version and program as a scriptPubKey: OP_n, push length, program.
+    fn script_pubkey(version: u8, program: &[u8]) -> String {
+        let op = if version == 0 { 0 } else { version + 0x50 };
+        let mut script = vec![op, program.len() as u8];
+        script.extend_from_slice(program);
+        hex::encode(&script)
+    }
+
+    #[test]
+    fn test_valid_checksums() {
+        let bech32 = [
+            "A12UEL5L",
+            "a12uel5l",
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            "?1ezyfcl",
+        ];
+        let bech32m = [
+            "A1LQFN3A",
+            "a1lqfn3a",
+            "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            "?1v759aa",
+        ];
+
+        for (strings, expected) in &[(&bech32, Variant::Bech32), (&bech32m, Variant::Bech32m)] {
+            for s in strings.iter() {
+                let (hrp, data, variant) = decode(s).unwrap();
+                assert_eq!(variant, *expected, "{}", s);
+                assert_eq!(encode(&hrp, &data, variant).unwrap(), s.to_lowercase());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_strings() {
+        let invalid = [
+            (" 1nwldj5", Bech32Error::InvalidCharacter { c: ' ', index: 0 }),
+            ("\u{7F}1axkwrx", Bech32Error::InvalidCharacter { c: '\u{7F}', index: 0 }),
+            ("\u{80}1eym55h", Bech32Error::InvalidCharacter { c: '\u{80}', index: 0 }),
+            ("abc1\u{2192}axkwrx", Bech32Error::InvalidCharacter { c: '\u{2192}', index: 4 }),
+            (
+                "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+                Bech32Error::InvalidLength(91),
+            ),
+            ("pzry9x0s0muk", Bech32Error::MissingSeparator),
+            ("1pzry9x0s0muk", Bech32Error::InvalidLength(0)),
+            ("x1b4n0q5v", Bech32Error::InvalidCharacter { c: 'b', index: 2 }),
+            ("li1dgmt3", Bech32Error::InvalidLength(5)),
+            ("de1lg7wt\u{ff}", Bech32Error::InvalidCharacter { c: '\u{ff}', index: 8 }),
+            ("A1G7SGD8", Bech32Error::InvalidChecksum),
+            ("10a06t8", Bech32Error::InvalidLength(7)),
+            ("1qzzfhee", Bech32Error::InvalidLength(0)),
+            ("y1b0jsk6g", Bech32Error::InvalidCharacter { c: 'b', index: 2 }),
+            ("lt1igcx5c0", Bech32Error::InvalidCharacter { c: 'i', index: 3 }),
+            ("in1muywd", Bech32Error::InvalidLength(5)),
+            ("mm1crxm3i", Bech32Error::InvalidCharacter { c: 'i', index: 8 }),
+            ("au1s5cgom", Bech32Error::InvalidCharacter { c: 'o', index: 7 }),
+            ("M1VUXWEZ", Bech32Error::InvalidChecksum),
+            ("16plkw9", Bech32Error::InvalidLength(7)),
+            ("1p2gdwpf", Bech32Error::InvalidLength(0)),
+            ("ABC1DEFGOH", Bech32Error::InvalidCharacter { c: 'O', index: 8 }),
+            ("abc1Defgoh", Bech32Error::MixedCase(4)),
+        ];
+
+        for (s, error) in invalid.iter() {
+            assert_eq!(decode(s).unwrap_err(), *error, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_valid_segwit_addresses() {
+        let valid = [
+            ("bc", "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            (
+                "tb",
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc",
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            ("bc", "BC1SW50QGDZ25J", "6002751e"),
+            ("bc", "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", "5210751e76e8199196d454941c45d1b3a323"),
+            (
+                "tb",
+                "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy",
+                "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+            (
+                "tb",
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+                "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+            (
+                "bc",
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+        ];
+
+        for (hrp, address, script) in valid.iter() {
+            let (version, program) = decode_segwit(hrp, address).unwrap();
+            assert_eq!(script_pubkey(version, &program), *script, "{}", address);
+            assert_eq!(
+                encode_segwit(hrp, version, &program).unwrap(),
+                address.to_lowercase()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_segwit_addresses() {
+        let invalid = [
+            "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+            "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+            "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+            "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+            "bc1pw5dgrnzv",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
+            "bc1gmk9yu",
+        ];
+
+        for address in invalid.iter() {
+            let hrp = if address.to_lowercase().starts_with("tb") {
+                "tb"
+            } else {
+                "bc"
+            };
+            assert!(decode_segwit(hrp, address).is_err(), "{}", address);
+        }
+
+        assert!(matches!(
+            decode_segwit(
+                "bc",
+                "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut"
+            ),
+            Err(Bech32Error::InvalidHrp { .. })
+        ));
+        assert_eq!(
+            decode_segwit("bc", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh"),
+            Err(Bech32Error::InvalidVariant {
+                expected: Variant::Bech32,
+                got: Variant::Bech32m
+            })
+        );
+        assert_eq!(
+            decode_segwit(
+                "bc",
+                "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4"
+            ),
+            Err(Bech32Error::InvalidCharacter { c: 'o', index: 59 })
+        );
+        assert_eq!(
+            decode_segwit(
+                "bc",
+                "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R"
+            ),
+            Err(Bech32Error::InvalidWitnessVersion(17))
+        );
+        assert_eq!(
+            decode_segwit("bc", "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P"),
+            Err(Bech32Error::InvalidProgramLength(16))
+        );
+        assert_eq!(
+            decode_segwit(
+                "tb",
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq"
+            ),
+            Err(Bech32Error::MixedCase(58))
+        );
+        assert_eq!(
+            decode_segwit(
+                "tb",
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j"
+            ),
+            Err(Bech32Error::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn test_handshake_address() {
+        let program = hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+
+        let address = encode_address("hs", 0, &program, Variant::Bech32).unwrap();
+        assert!(address.starts_with("hs1q"));
+        assert_eq!(
+            decode_address("hs", &address).unwrap(),
+            (0, program.clone(), Variant::Bech32)
+        );
+
+        //Handshake keeps Bech32 for versions above 0, which BIP350 would reject.
+        let address = encode_address("hs", 31, &program, Variant::Bech32).unwrap();
+        assert_eq!(
+            decode_address("hs", &address).unwrap(),
+            (31, program, Variant::Bech32)
+        );
+        assert!(decode_segwit("hs", &address).is_err());
+    }
+
+    #[test]
+    fn test_convert_bits() {
+        assert_eq!(convert_bits(&[0xff], 8, 5, true).unwrap(), vec![0x1f, 0x1c]);
+        assert_eq!(
+            convert_bits(&[0x1f, 0x1c], 5, 8, false).unwrap(),
+            vec![0xff]
+        );
+        assert_eq!(
+            convert_bits(&[0x01], 8, 2, true).unwrap(),
+            vec![0x00, 0x00, 0x00, 0x01]
+        );
+        assert_eq!(
+            convert_bits(&[0x1f, 0x1d], 5, 8, false),
+            Err(Bech32Error::InvalidPadding)
+        );
+        assert_eq!(
+            convert_bits(&[0x01, 0x20], 5, 8, false),
+            Err(Bech32Error::InvalidData {
+                value: 0x20,
+                from: 5,
+                index: 1
+            })
+        );
+        assert_eq!(
+            convert_bits(&[0x01], 0, 5, true),
+            Err(Bech32Error::InvalidBits { from: 0, to: 5 })
+        );
+        assert_eq!(
+            convert_bits(&[0x01], 8, 32, true),
+            Err(Bech32Error::InvalidBits { from: 8, to: 32 })
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Lowercase hex, without the `encodings` crate so that it's available without `std`.
+//!
+//! With the `std` feature the types in this crate also implement `encodings`' `ToHex` and
+//! `FromHex`, which are built on these functions.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+const CHARS: &[u8; 16] = b"0123456789abcdef";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    InvalidCharacter { c: char, index: usize },
+    OddLength,
+    InvalidLength { expected: usize, got: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            HexError::OddLength => write!(f, "Odd number of digits"),
+            HexError::InvalidLength { expected, got } => {
+                write!(f, "Invalid Length: expected {expected} bytes, got {got}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError {}
+
+#[cfg(feature = "std")]
+impl From<HexError> for encodings::FromHexError {
+    fn from(e: HexError) -> Self {
+        match e {
+            HexError::InvalidCharacter { c, index } => {
+                encodings::FromHexError::InvalidHexCharacter { c, index }
+            }
+            HexError::OddLength => encodings::FromHexError::OddLength,
+            HexError::InvalidLength { .. } => encodings::FromHexError::InvalidHexLength,
+        }
+    }
+}
+
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len() * 2);
+    for byte in data {
+        encoded.push(CHARS[(byte >> 4) as usize] as char);
+        encoded.push(CHARS[(byte & 0xf) as usize] as char);
+    }
+    encoded
+}
+
+//Writes the hex straight to the formatter, for Display and Debug impls.
+pub fn write(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    for byte in data {
+        write!(f, "{byte:02x}")?;
+    }
+    Ok(())
+}
+
+//Accepts upper and lowercase digits.
+pub fn decode<T: AsRef<[u8]>>(hex: T) -> Result<Vec<u8>, HexError> {
+    let hex = hex.as_ref();
+    if hex.len() % 2 != 0 {
+        return Err(HexError::OddLength);
+    }
+
+    hex.chunks(2)
+        .enumerate()
+        .map(|(i, pair)| Ok(digit(pair[0], 2 * i)? << 4 | digit(pair[1], 2 * i + 1)?))
+        .collect()
+}
+
+//Decodes exactly 32 bytes.
+pub fn decode_array<T: AsRef<[u8]>>(hex: T) -> Result<[u8; 32], HexError> {
+    let bytes = decode(hex)?;
+    if bytes.len() != 32 {
+        return Err(HexError::InvalidLength {
+            expected: 32,
+            got: bytes.len(),
+        });
+    }
+
+    let mut array = [0; 32];
+    array.copy_from_slice(&bytes);
+    Ok(array)
+}
+
+fn digit(c: u8, index: usize) -> Result<u8, HexError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(HexError::InvalidCharacter {
+            c: c as char,
+            index,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(encode(&[0x00, 0xAB, 0xFF]), "00abff");
+        assert_eq!(decode("00aBfF").unwrap(), vec![0x00, 0xAB, 0xFF]);
+        assert_eq!(decode("abc"), Err(HexError::OddLength));
+        assert_eq!(
+            decode("0g"),
+            Err(HexError::InvalidCharacter { c: 'g', index: 1 })
+        );
+        assert_eq!(
+            decode_array("00"),
+            Err(HexError::InvalidLength {
+                expected: 32,
+                got: 1
+            })
+        );
+        assert_eq!(decode_array("ff".repeat(32)).unwrap(), [0xFF; 32]);
+    }
+
+    #[test]
+    fn test_hex_write() {
+        struct Bytes<'a>(&'a [u8]);
+
+        impl fmt::Display for Bytes<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write(f, self.0)
+            }
+        }
+
+        assert_eq!(Bytes(&[]).to_string(), "");
+        assert_eq!(Bytes(&[0x01, 0xEF]).to_string(), "01ef");
+    }
+
+    #[test]
+    fn test_hex_error_display() {
+        assert_eq!(
+            HexError::InvalidCharacter { c: 'z', index: 3 }.to_string(),
+            "Invalid character 'z' at position 3"
+        );
+        assert_eq!(HexError::OddLength.to_string(), "Odd number of digits");
+        assert_eq!(
+            HexError::InvalidLength {
+                expected: 32,
+                got: 31
+            }
+            .to_string(),
+            "Invalid Length: expected 32 bytes, got 31"
+        );
+        assert!(decode("").unwrap().is_empty());
+    }
+}
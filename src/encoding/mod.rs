@@ -0,0 +1,20 @@
+//! Text encodings for binary payloads such as [`Hash`](crate::Hash) and
+//! [`Buffer`](crate::Buffer).
+//!
+//! * [`hex`] - Lowercase hex, also used by `Display` and serde.
+//! * [`base58`] - Base58 and Base58Check, used by P2PKH and P2SH addresses.
+//! * [`bech32`] - Bech32 (BIP173) and Bech32m (BIP350), used by segwit and Handshake addresses.
+//! * [`base64`] - Standard and URL-safe Base64, for JSON APIs.
+//! * [`base32`] - Base32 and z-base-32, for DNS-safe labels.
+
+pub mod base32;
+pub mod base58;
+pub mod base64;
+pub mod bech32;
+pub mod hex;
+
+pub use self::base32::Base32Error;
+pub use self::base58::Base58Error;
+pub use self::base64::Base64Error;
+pub use self::bech32::{Bech32Error, Variant};
+pub use self::hex::HexError;
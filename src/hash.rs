@@ -1,15 +1,67 @@
+use crate::encoding::hex;
+use crate::{Buffer, Uint256};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
 use encodings::hex::{FromHex, FromHexError, ToHex};
-use std::fmt;
-use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HashError {
+    InvalidLength { expected: usize, got: usize },
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashError::InvalidLength { expected, got } => {
+                write!(f, "Invalid Length: expected {expected} bytes, got {got}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HashError {}
 
 #[derive(Copy, Clone, PartialEq, Eq, Default, PartialOrd, Ord, Hash)]
 //Possibly make this generic on the size?
 //Not sure if we'll need that, but just a reminder
 //I think we might actually want to implement this as a trait?
-//@todo from<Uint256>
 pub struct Hash([u8; 32]);
 
 impl Hash {
+    /// Creates a hash from its raw bytes, usable in `const` contexts.
+    #[inline]
+    pub const fn from_bytes(bytes: [u8; 32]) -> Hash {
+        Hash(bytes)
+    }
+
+    /// Parses exactly 64 hex digits, in the same byte order as `ToHex`, in a `const` context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is not 64 hex digits long. Inside a `const` this is a compile error,
+    /// which is what the [`hash!`](macro@crate::hash) macro relies on.
+    pub const fn from_hex_const(hex: &str) -> Hash {
+        let bytes = hex.as_bytes();
+        if bytes.len() != 64 {
+            panic!("hash literal must be exactly 64 hex digits");
+        }
+
+        let mut ret = [0u8; 32];
+        let mut i = 0;
+        while i < 32 {
+            let high = crate::macros::hex_digit(bytes[2 * i]);
+            let low = crate::macros::hex_digit(bytes[2 * i + 1]);
+            ret[i] = (high << 4) | low;
+            i += 1;
+        }
+
+        Hash(ret)
+    }
+
     pub fn to_array(&self) -> [u8; 32] {
         self.0
     }
@@ -22,18 +74,124 @@ impl Hash {
         }
         true
     }
+
+    /// Returns the hash with its bytes in reverse order.
+    pub fn reversed(&self) -> Hash {
+        let mut bytes = self.0;
+        bytes.reverse();
+        Hash(bytes)
+    }
 }
 
-//Needs to be TryFrom
-//Need more checks here for length, and errors
-impl From<Vec<u8>> for Hash {
-    fn from(hex_vec: Vec<u8>) -> Self {
+/// A streaming hash function with a 32 byte digest, used to bind a hashing algorithm to a
+/// type declared with [`hash_newtype!`](crate::hash_newtype).
+pub trait HashEngine: Default {
+    /// Feeds `data` into the engine.
+    fn input(&mut self, data: &[u8]);
+
+    /// Consumes the engine and returns the digest.
+    fn finalize(self) -> Hash;
+}
+
+/// SHA-256.
+#[derive(Default, Clone)]
+pub struct Sha256(sha2::Sha256);
+
+impl HashEngine for Sha256 {
+    fn input(&mut self, data: &[u8]) {
+        sha2::Digest::update(&mut self.0, data);
+    }
+
+    fn finalize(self) -> Hash {
         let mut array = [0; 32];
-        array.copy_from_slice(&hex_vec);
+        array.copy_from_slice(&sha2::Digest::finalize(self.0));
         Hash(array)
     }
 }
 
+/// Double SHA-256, as used for Bitcoin txids, block hashes and Base58Check checksums.
+#[derive(Default, Clone)]
+pub struct Sha256d(Sha256);
+
+impl HashEngine for Sha256d {
+    fn input(&mut self, data: &[u8]) {
+        self.0.input(data);
+    }
+
+    fn finalize(self) -> Hash {
+        let mut engine = Sha256::default();
+        engine.input(&self.0.finalize().0);
+        engine.finalize()
+    }
+}
+
+hash_newtype!(
+    /// A transaction id. Displayed, parsed and serialized as hex in reversed byte order, the way
+    /// bitcoind and hsd print it.
+    TxId,
+    Hash,
+    reversed_display = true,
+    engine = Sha256d
+);
+
+hash_newtype!(
+    /// A block hash. Displayed, parsed and serialized as hex in reversed byte order, the way
+    /// bitcoind and hsd print it.
+    BlockHash,
+    Hash,
+    reversed_display = true,
+    engine = Sha256d
+);
+
+impl TryFrom<&[u8]> for Hash {
+    type Error = HashError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != 32 {
+            return Err(HashError::InvalidLength {
+                expected: 32,
+                got: bytes.len(),
+            });
+        }
+
+        let mut array = [0; 32];
+        array.copy_from_slice(bytes);
+        Ok(Hash(array))
+    }
+}
+
+impl TryFrom<Vec<u8>> for Hash {
+    type Error = HashError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Hash::try_from(&bytes[..])
+    }
+}
+
+/// Uses the whole contents of the buffer, regardless of its read offset.
+impl TryFrom<Buffer> for Hash {
+    type Error = HashError;
+
+    fn try_from(buffer: Buffer) -> Result<Self, Self::Error> {
+        Hash::try_from(buffer.to_bytes())
+    }
+}
+
+/// Reads the hash bytes as a little-endian number, which is how Bitcoin and Handshake compare a
+/// block hash against its target.
+impl From<Hash> for Uint256 {
+    fn from(hash: Hash) -> Self {
+        Uint256::from_le_bytes(hash.0)
+    }
+}
+
+/// Writes the number as little-endian bytes, the inverse of `From<Hash> for Uint256`.
+impl From<Uint256> for Hash {
+    fn from(num: Uint256) -> Self {
+        Hash(num.to_le_bytes())
+    }
+}
+
 //This should only be implemented on Blake2b hash
 //Redo this when we split to blake2b/ run into problems TODO
 impl From<[u8; 32]> for Hash {
@@ -48,51 +206,74 @@ impl AsRef<[u8]> for Hash {
     }
 }
 
+#[cfg(feature = "std")]
 impl FromHex for Hash {
     type Error = FromHexError;
-    fn from_hex<T: AsRef<[u8]>>(hex: T) -> std::result::Result<Self, Self::Error> {
-        let bytes = Vec::from_hex(hex)?;
-        if bytes.len() != 32 {
-            Err(FromHexError::InvalidHexLength)
-        } else {
-            let mut ret = [0; 32];
-            ret.copy_from_slice(&bytes);
-            Ok(Hash::from(ret))
-        }
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        Ok(Hash(hex::decode_array(hex)?))
     }
 }
 
+#[cfg(feature = "std")]
 impl ToHex for Hash {
     fn to_hex(&self) -> String {
-        self.0.to_vec().to_hex()
+        hex::encode(&self.0)
     }
 }
 
 impl FromStr for Hash {
-    type Err = FromHexError;
+    type Err = hex::HexError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Hash::from_hex(s)
+        Ok(Hash(hex::decode_array(s)?))
     }
 }
 
 impl fmt::Display for Hash {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_hex())
+        hex::write(f, &self.0)
     }
 }
 
 impl fmt::Debug for Hash {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_hex())
+        hex::write(f, &self.0)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Hash {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Hash::from(<[u8; 32]>::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <[u8; 32]>::size_hint(depth)
+    }
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for Hash {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Hash>;
+
+    //Biased towards the null hash, which often has a special meaning.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        prop_oneof![
+            1 => Just(Hash::default()),
+            9 => any::<[u8; 32]>().prop_map(Hash::from),
+        ]
+        .boxed()
     }
 }
 
 #[cfg(feature = "serialization")]
 impl serde::Serialize for Hash {
-    fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
         if s.is_human_readable() {
-            s.serialize_str(&self.to_hex())
+            s.serialize_str(&hex::encode(&self.0))
         } else {
             s.serialize_bytes(&self.to_array())
         }
@@ -101,33 +282,33 @@ impl serde::Serialize for Hash {
 
 #[cfg(feature = "serialization")]
 impl<'de> serde::Deserialize<'de> for Hash {
-    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Hash, D::Error> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Hash, D::Error> {
         if d.is_human_readable() {
             struct HexVisitor;
 
             impl<'de> serde::de::Visitor<'de> for HexVisitor {
                 type Value = Hash;
 
-                fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     formatter.write_str("an ASCII hex string")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    if let Ok(hex) = ::std::str::from_utf8(v) {
-                        Hash::from_hex(hex).map_err(E::custom)
+                    if let Ok(string) = core::str::from_utf8(v) {
+                        hex::decode_array(string).map(Hash).map_err(E::custom)
                     } else {
-                        return Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self));
+                        Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self))
                     }
                 }
 
-                fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+                fn visit_str<E>(self, v: &str) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    Hash::from_hex(v).map_err(E::custom)
+                    hex::decode_array(v).map(Hash).map_err(E::custom)
                 }
             }
 
@@ -138,11 +319,11 @@ impl<'de> serde::Deserialize<'de> for Hash {
             impl<'de> ::serde::de::Visitor<'de> for BytesVisitor {
                 type Value = Hash;
 
-                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                     formatter.write_str("a bytestring")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
@@ -162,3 +343,222 @@ impl<'de> serde::Deserialize<'de> for Hash {
 }
 
 //TODO need to test this, and add testing for serde stuff.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Buffer;
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_hash_const() {
+        const GENESIS: Hash =
+            hash!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+
+        assert_eq!(
+            GENESIS.to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(Hash::from_bytes([0; 32]), Hash::default());
+    }
+
+    #[test]
+    fn test_sha256() {
+        let mut engine = Sha256::default();
+        engine.input(b"ab");
+        engine.input(b"c");
+
+        assert_eq!(
+            engine.finalize().to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sha256d() {
+        //Bitcoin's genesis block header.
+        let header = hex::decode(
+            "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b\
+             12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c",
+        )
+        .unwrap();
+
+        assert_eq!(
+            BlockHash::hash(&header).to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+    }
+
+    #[test]
+    fn test_try_from() {
+        let bytes: Vec<u8> = (0..32).collect();
+        let hash = Hash::try_from(bytes.clone()).unwrap();
+
+        assert_eq!(hash.to_array()[..], bytes[..]);
+        assert_eq!(Hash::try_from(&bytes[..]).unwrap(), hash);
+        assert_eq!(Hash::try_from(Buffer::from(bytes.clone())).unwrap(), hash);
+
+        assert_eq!(
+            Hash::try_from(&bytes[..31]),
+            Err(HashError::InvalidLength {
+                expected: 32,
+                got: 31
+            })
+        );
+        assert_eq!(
+            Hash::try_from(vec![0; 33]),
+            Err(HashError::InvalidLength {
+                expected: 32,
+                got: 33
+            })
+        );
+        assert!(Hash::try_from(Buffer::new()).is_err());
+    }
+
+    #[test]
+    fn test_uint256_conversion() {
+        let hash = hash!("0100000000000000000000000000000000000000000000000000000000000002");
+        let num = Uint256::from(hash);
+
+        assert_eq!(num, Uint256::from_limbs([1, 0, 0, 0x0200000000000000]));
+        assert_eq!(Hash::from(num), hash);
+
+        //A block hash meets its target when it's numerically below it.
+        let block_hash = BlockHash::from(
+            hash!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").reversed(),
+        );
+        let target = uint256!("0x00000000ffff0000000000000000000000000000000000000000000000000000");
+        assert!(Uint256::from(block_hash.to_hash()) < target);
+    }
+
+    #[test]
+    fn test_parse_reversed() {
+        let hex = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+        let block_hash: BlockHash = hex.parse().unwrap();
+        let hash: Hash = hex.parse().unwrap();
+
+        assert_eq!(block_hash.to_string(), hex);
+        assert_eq!(block_hash.to_hash(), hash.reversed());
+        assert_eq!(hex.parse::<TxId>().unwrap().to_hash(), hash.reversed());
+        assert!("zz".parse::<Hash>().is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reversed_display() {
+        let hex = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let natural = "6fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000";
+
+        let block_hash: BlockHash = hex.parse().unwrap();
+
+        assert_eq!(block_hash.to_string(), hex);
+        assert_eq!(format!("{:?}", block_hash), hex);
+        assert_eq!(block_hash.to_hash().to_hex(), natural);
+        assert_eq!(
+            BlockHash::from(Hash::from_hex(natural).unwrap()),
+            block_hash
+        );
+
+        let txid = TxId::from_hex(hex).unwrap();
+        assert_eq!(Hash::from(txid), block_hash.to_hash());
+        assert_eq!(txid.to_hex(), hex);
+    }
+
+    #[derive(Default)]
+    struct XorEngine([u8; 32], usize);
+
+    impl HashEngine for XorEngine {
+        fn input(&mut self, data: &[u8]) {
+            for byte in data {
+                self.0[self.1 % 32] ^= byte;
+                self.1 += 1;
+            }
+        }
+
+        fn finalize(self) -> Hash {
+            Hash::from(self.0)
+        }
+    }
+
+    hash_newtype!(XorHash, Hash, engine = XorEngine);
+    hash_newtype!(XorTxId, TxId, reversed_display = true, engine = XorEngine);
+
+    #[test]
+    fn test_hash_newtype() {
+        let hash = XorHash::hash(&[1, 2, 3]);
+        let txid = XorTxId::hash(&[1, 2, 3]);
+
+        assert_eq!(hash.to_array()[..3], [1, 2, 3]);
+        assert_eq!(txid.to_array(), hash.to_array());
+
+        assert_eq!(&hash.to_string()[..6], "010203");
+        assert_eq!(&txid.to_string()[58..], "030201");
+        assert_eq!(
+            format!("{:?}", txid),
+            TxId::from(txid.to_array()).to_string()
+        );
+
+        //Derefs down to the inner hash.
+        assert!(!hash.is_null());
+        assert_eq!(Hash::from(hash), hash.to_hash());
+
+        let mut buffer = Buffer::new();
+        hash.encode(&mut buffer);
+        txid.encode(&mut buffer);
+
+        assert_eq!(buffer[..32], hash.to_array());
+        assert_eq!(XorHash::decode(&mut buffer).unwrap(), hash);
+        assert_eq!(XorTxId::decode(&mut buffer).unwrap(), txid);
+        assert!(XorHash::decode(&mut buffer).is_err());
+
+        assert_eq!(hash.to_string().parse::<XorHash>().unwrap(), hash);
+        assert_eq!(txid.to_string().parse::<XorTxId>().unwrap(), txid);
+        assert_eq!(
+            "0011".parse::<XorHash>(),
+            Err(hex::HexError::InvalidLength {
+                expected: 32,
+                got: 2
+            })
+        );
+    }
+
+    //FromHex and ToHex come from __hash_newtype_hex!, which is empty without std, so
+    //test_hash_newtype above also checks the newtypes build without them.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash_newtype_hex() {
+        let hash = XorHash::hash(&[1, 2, 3]);
+        let txid = XorTxId::hash(&[1, 2, 3]);
+
+        assert_eq!(hash.to_hex(), hash.to_string());
+        assert_eq!(txid.to_hex(), TxId::from(txid.to_array()).to_hex());
+        assert_eq!(XorTxId::from_hex(txid.to_hex()).unwrap(), txid);
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn test_reversed_serde() {
+        use serde_test::{assert_tokens, Configure, Token};
+
+        static NATURAL: [u8; 32] = [
+            0x6f, 0xe2, 0x8c, 0x0a, 0xb6, 0xf1, 0xb3, 0x72, 0xc1, 0xa6, 0xa2, 0x46, 0xae, 0x63,
+            0xf7, 0x4f, 0x93, 0x1e, 0x83, 0x65, 0xe1, 0x5a, 0x08, 0x9c, 0x68, 0xd6, 0x19, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let hex = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let txid = TxId::from(NATURAL);
+
+        assert_tokens(&txid.readable(), &[Token::Str(hex)]);
+        assert_tokens(&txid.compact(), &[Token::Bytes(&NATURAL)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hash_from_hex_const_short() {
+        Hash::from_hex_const("00");
+    }
+}
@@ -1,9 +1,36 @@
+//Without the default `std` feature the crate only needs `core` and `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+pub mod array_buffer;
+pub mod buf;
 pub mod buffer;
+pub mod encoding;
 pub mod hash;
+#[cfg(feature = "serialization")]
+pub mod serde;
+pub mod size;
 pub mod uint256;
 pub mod varint;
 
-pub use buffer::{Buffer, BufferError};
-pub use hash::Hash;
-pub use uint256::Uint256;
-pub use varint::VarInt;
+pub use array_buffer::ArrayBuffer;
+pub use buf::{BufRead, BufWrite};
+pub use buffer::{Buffer, BufferError, Checkpoint, Placeholder};
+pub use hash::{BlockHash, Hash, HashError, TxId};
+pub use size::{EncodedLen, SizeCounter};
+pub use uint256::{Uint256, Uint256Error};
+pub use varint::{CoreVarInt, VarInt};
+
+//Used by the exported macros, so that crates using them don't need our dependencies.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::String;
+    #[cfg(feature = "std")]
+    pub use encodings::hex::{FromHex, FromHexError, ToHex};
+    #[cfg(feature = "serialization")]
+    pub use serde;
+}
@@ -0,0 +1,348 @@
+/// Builds a [`Uint256`](crate::Uint256) from a big-endian hex literal at compile time.
+///
+/// The `0x` prefix is optional. An invalid literal fails the build instead of panicking at
+/// runtime, so the macro can be used to declare constants such as a proof-of-work limit.
+///
+/// ```
+/// use extended_primitives::{uint256, Uint256};
+///
+/// const POW_LIMIT: Uint256 =
+///     uint256!("0x00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+/// assert_eq!(uint256!("0xff"), Uint256::from_u64_const(255));
+/// ```
+///
+/// ```compile_fail
+/// use extended_primitives::{uint256, Uint256};
+///
+/// const BAD: Uint256 = uint256!("0xnothex");
+/// ```
+#[macro_export]
+macro_rules! uint256 {
+    ($hex:expr) => {{
+        const VALUE: $crate::Uint256 = $crate::Uint256::from_hex_const($hex);
+        VALUE
+    }};
+}
+
+/// Builds a [`Hash`](crate::Hash) from a 64 digit hex literal at compile time.
+///
+/// The literal uses the same byte order as `Hash::to_hex`. An invalid literal fails the build.
+///
+/// ```
+/// use extended_primitives::{hash, Hash};
+///
+/// const GENESIS: Hash = hash!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+/// assert!(!GENESIS.is_null());
+/// ```
+///
+/// ```compile_fail
+/// use extended_primitives::{hash, Hash};
+///
+/// const BAD: Hash = hash!("0011");
+/// ```
+#[macro_export]
+macro_rules! hash {
+    ($hex:expr) => {{
+        const VALUE: $crate::Hash = $crate::Hash::from_hex_const($hex);
+        VALUE
+    }};
+}
+
+//Used by the const hex parsers, panics so that a bad literal is a compile error.
+pub(crate) const fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => panic!("invalid hex character in literal"),
+    }
+}
+
+//Reads without advancing the offset, by decoding a copy of the slice that's left with BufRead,
+//so peeking only needs &self like peek_bytes. Used by Buffer and ArrayBuffer.
+macro_rules! peek {
+    ($peek:ident, $read:ident, $ty:ty) => {
+        pub fn $peek(&self) -> Result<$ty> {
+            let bytes = self.to_bytes();
+            let mut rest = &bytes[self.offset().min(bytes.len())..];
+            BufRead::$read(&mut rest)
+        }
+    };
+}
+
+/// Declares a distinct hash type wrapping [`Hash`](crate::Hash) (or another type declared with
+/// this macro), so that e.g. a transaction id can't be passed where a merkle root is expected.
+///
+/// The generated type derefs to the inner type and has `From`/`Into` conversions both ways, hex
+/// (`Display`, `Debug` and `FromStr`, plus `ToHex` and `FromHex` with the `std` feature), serde
+/// support with the `serialization` feature, and `encode`/`decode` methods for
+/// [`Buffer`](crate::Buffer).
+///
+/// Options:
+///
+/// * `reversed_display = true` makes every hex form byte-reversed, the way bitcoind and hsd print
+///   block and transaction ids. Binary serialization keeps the natural byte order.
+/// * `engine = Type` binds a [`HashEngine`](crate::hash::HashEngine), which adds
+///   `Type::hash(data)`.
+///
+/// ```
+/// use extended_primitives::{hash_newtype, Hash};
+///
+/// hash_newtype!(
+///     /// A Handshake name hash.
+///     NameHash,
+///     Hash
+/// );
+/// hash_newtype!(Txid, Hash, reversed_display = true);
+///
+/// let mut bytes = [0; 32];
+/// bytes[0] = 0xff;
+/// let txid = Txid::from(bytes);
+/// assert!(txid.to_string().ends_with("ff"));
+/// assert!(NameHash::default().is_null());
+/// ```
+#[macro_export]
+macro_rules! hash_newtype {
+    ($(#[$attr:meta])* $name:ident, $inner:ty $(,)?) => {
+        $crate::hash_newtype!(@impl $(#[$attr])* $name, $inner, false);
+    };
+    ($(#[$attr:meta])* $name:ident, $inner:ty, reversed_display = $rev:literal $(,)?) => {
+        $crate::hash_newtype!(@impl $(#[$attr])* $name, $inner, $rev);
+    };
+    ($(#[$attr:meta])* $name:ident, $inner:ty, engine = $engine:ty $(,)?) => {
+        $crate::hash_newtype!(@impl $(#[$attr])* $name, $inner, false);
+        $crate::hash_newtype!(@engine $name, $engine);
+    };
+    ($(#[$attr:meta])* $name:ident, $inner:ty, reversed_display = $rev:literal, engine = $engine:ty $(,)?) => {
+        $crate::hash_newtype!(@impl $(#[$attr])* $name, $inner, $rev);
+        $crate::hash_newtype!(@engine $name, $engine);
+    };
+    (@engine $name:ident, $engine:ty) => {
+        impl $name {
+            /// Hashes `data` with the engine bound to this type.
+            pub fn hash(data: &[u8]) -> Self {
+                let mut engine = <$engine as ::core::default::Default>::default();
+                $crate::hash::HashEngine::input(&mut engine, data);
+                $name::from($crate::hash::HashEngine::finalize(engine).to_array())
+            }
+        }
+    };
+    (@impl $(#[$attr:meta])* $name:ident, $inner:ty, $rev:literal) => {
+        $(#[$attr])*
+        #[derive(
+            Copy,
+            Clone,
+            PartialEq,
+            Eq,
+            Default,
+            PartialOrd,
+            Ord,
+            ::core::hash::Hash,
+        )]
+        pub struct $name($inner);
+
+        //Matches `Hash`, which takes `&self` in its conversions.
+        #[allow(clippy::wrong_self_convention)]
+        impl $name {
+            /// Whether the hex forms of this type are byte-reversed.
+            pub const REVERSED_DISPLAY: bool = $rev;
+
+            /// Wraps a hash that is in natural (internal) byte order.
+            #[inline]
+            pub const fn from_hash(hash: $inner) -> Self {
+                $name(hash)
+            }
+
+            /// Returns the wrapped hash, in natural (internal) byte order.
+            #[inline]
+            pub fn to_hash(&self) -> $inner {
+                self.0
+            }
+
+            /// Returns the bytes in natural (internal) byte order.
+            #[inline]
+            pub fn to_array(&self) -> [u8; 32] {
+                self.0.to_array()
+            }
+
+            /// Writes the hash to `buffer` in natural byte order.
+            pub fn encode(&self, buffer: &mut $crate::Buffer) {
+                buffer.write_bytes(&self.to_array());
+            }
+
+            //The bytes in the order the hex forms use.
+            fn to_display_array(&self) -> [u8; 32] {
+                let mut array = self.to_array();
+                if $rev {
+                    array.reverse();
+                }
+                array
+            }
+
+            //Parses the hex forms.
+            fn from_display_hex<T: ::core::convert::AsRef<[u8]>>(
+                hex: T,
+            ) -> ::core::result::Result<Self, $crate::encoding::HexError> {
+                let mut array = $crate::encoding::hex::decode_array(hex)?;
+                if $rev {
+                    array.reverse();
+                }
+                Ok($name::from(array))
+            }
+
+            /// Reads a hash in natural byte order from `buffer`.
+            pub fn decode(
+                buffer: &mut $crate::Buffer,
+            ) -> ::core::result::Result<Self, $crate::BufferError> {
+                Ok($name::from(buffer.read_hash()?.to_array()))
+            }
+        }
+
+        impl $crate::EncodedLen for $name {
+            fn encoded_len(&self) -> usize {
+                32
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = $inner;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::core::convert::From<$inner> for $name {
+            fn from(hash: $inner) -> Self {
+                $name(hash)
+            }
+        }
+
+        impl ::core::convert::From<$name> for $inner {
+            fn from(hash: $name) -> Self {
+                hash.0
+            }
+        }
+
+        impl ::core::convert::From<[u8; 32]> for $name {
+            fn from(bytes: [u8; 32]) -> Self {
+                $name(<$inner>::from(bytes))
+            }
+        }
+
+        impl ::core::convert::AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                self.0.as_ref()
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                $crate::encoding::hex::write(f, &self.to_display_array())
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                $crate::encoding::hex::write(f, &self.to_display_array())
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::encoding::HexError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                $name::from_display_hex(s)
+            }
+        }
+
+        $crate::__hash_newtype_hex!($name, $rev);
+        $crate::__hash_newtype_serde!($name, $rev);
+    };
+}
+
+//Split out of `hash_newtype!` so the `std` feature is checked against this crate, and not against
+//the crate the newtype is declared in.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_hex {
+    ($name:ident, $rev:literal) => {
+        impl $crate::__private::FromHex for $name {
+            type Error = $crate::__private::FromHexError;
+
+            fn from_hex<T: ::core::convert::AsRef<[u8]>>(
+                hex: T,
+            ) -> ::core::result::Result<Self, Self::Error> {
+                Ok($name::from_display_hex(hex)?)
+            }
+        }
+
+        impl $crate::__private::ToHex for $name {
+            fn to_hex(&self) -> $crate::__private::String {
+                $crate::encoding::hex::encode(&self.to_display_array())
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_hex {
+    ($name:ident, $rev:literal) => {};
+}
+
+//Split out of `hash_newtype!` so the `serialization` feature is checked against this crate, and
+//not against the crate the newtype is declared in.
+#[cfg(feature = "serialization")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_serde {
+    ($name:ident, $rev:literal) => {
+        impl $crate::__private::serde::Serialize for $name {
+            fn serialize<S: $crate::__private::serde::Serializer>(
+                &self,
+                s: S,
+            ) -> ::core::result::Result<S::Ok, S::Error> {
+                if s.is_human_readable() {
+                    s.serialize_str(&$crate::encoding::hex::encode(&self.to_display_array()))
+                } else {
+                    s.serialize_bytes(&self.to_array())
+                }
+            }
+        }
+
+        impl<'de> $crate::__private::serde::Deserialize<'de> for $name {
+            fn deserialize<D: $crate::__private::serde::Deserializer<'de>>(
+                d: D,
+            ) -> ::core::result::Result<$name, D::Error> {
+                let reversed = $rev && d.is_human_readable();
+                let mut array =
+                    <$crate::Hash as $crate::__private::serde::Deserialize>::deserialize(d)?
+                        .to_array();
+                if reversed {
+                    array.reverse();
+                }
+                Ok($name::from(array))
+            }
+        }
+
+        impl $crate::serde::FixedBytes for $name {
+            fn to_le_array(&self) -> [u8; 32] {
+                self.to_array()
+            }
+
+            fn from_le_array(bytes: [u8; 32]) -> Self {
+                $name::from(bytes)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serialization"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_serde {
+    ($name:ident, $rev:literal) => {};
+}
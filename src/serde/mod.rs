@@ -0,0 +1,397 @@
+//! Serde adapters for use with `#[serde(with = "...")]`, so a field can pick its wire format
+//! instead of the default hex, e.g. to match the JSON-RPC output of bitcoind and hsd.
+//!
+//! * [`base64`], [`base64_url`], [`base32`] and [`zbase32`] work on anything that is
+//!   `AsRef<[u8]>` when serializing and `TryFrom<Vec<u8>>` when deserializing, which covers
+//!   [`Buffer`](crate::Buffer), [`Hash`](struct@crate::Hash) and `Vec<u8>`.
+//! * [`hex_be`], [`hex_le`] and [`bytes_fixed`] work on 32 byte values, see [`FixedBytes`].
+//! * [`decimal_string`] and [`u64_if_fits`] are for [`Uint256`].
+//!
+//! ```
+//! use extended_primitives::Buffer;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Resource {
+//!     #[serde(with = "extended_primitives::serde::base64")]
+//!     data: Buffer,
+//! }
+//! ```
+
+use crate::encoding::{self, hex};
+use crate::{Hash, Uint256};
+use ::serde::de::Error;
+use ::serde::{Deserialize, Deserializer, Serializer};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+
+/// A 32 byte value with a little-endian byte order, used by [`hex_be`], [`hex_le`] and
+/// [`bytes_fixed`].
+///
+/// For a [`Uint256`] that is the number's little-endian bytes. A [`Hash`](struct@Hash) and the types declared
+/// with [`hash_newtype!`](crate::hash_newtype) are little-endian as stored, the same convention
+/// `From<Hash> for Uint256` uses, so `hex_be` prints them reversed the way bitcoind does.
+pub trait FixedBytes: Sized {
+    fn to_le_array(&self) -> [u8; 32];
+
+    fn from_le_array(bytes: [u8; 32]) -> Self;
+}
+
+impl FixedBytes for Uint256 {
+    fn to_le_array(&self) -> [u8; 32] {
+        self.to_le_bytes()
+    }
+
+    fn from_le_array(bytes: [u8; 32]) -> Self {
+        Uint256::from_le_bytes(bytes)
+    }
+}
+
+impl FixedBytes for Hash {
+    fn to_le_array(&self) -> [u8; 32] {
+        self.to_array()
+    }
+
+    fn from_le_array(bytes: [u8; 32]) -> Self {
+        Hash::from(bytes)
+    }
+}
+
+macro_rules! string_adapter {
+    ($name:ident, $doc:expr, $encode:path, $decode:path) => {
+        #[doc = $doc]
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<T, S>(value: &T, s: S) -> Result<S::Ok, S::Error>
+            where
+                T: AsRef<[u8]>,
+                S: Serializer,
+            {
+                s.serialize_str(&$encode(value.as_ref()))
+            }
+
+            pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+            where
+                T: TryFrom<Vec<u8>>,
+                T::Error: Display,
+                D: Deserializer<'de>,
+            {
+                let encoded = String::deserialize(d)?;
+                let bytes = $decode(&encoded).map_err(D::Error::custom)?;
+                T::try_from(bytes).map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+string_adapter!(
+    base64,
+    "Standard, padded Base64.",
+    encoding::base64::encode,
+    encoding::base64::decode
+);
+string_adapter!(
+    base64_url,
+    "URL-safe Base64 without padding.",
+    encoding::base64::encode_url,
+    encoding::base64::decode_url
+);
+string_adapter!(
+    base32,
+    "Lowercase Base32 without padding.",
+    encoding::base32::encode,
+    encoding::base32::decode
+);
+string_adapter!(
+    zbase32,
+    "z-base-32.",
+    encoding::base32::encode_zbase32,
+    encoding::base32::decode_zbase32
+);
+
+//Parses 64 hex digits, with an optional 0x prefix.
+fn parse_hex<E: Error>(hex: &str) -> Result<[u8; 32], E> {
+    let digits = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+    let bytes = hex::decode(digits).map_err(E::custom)?;
+    if bytes.len() != 32 {
+        return Err(E::invalid_length(bytes.len(), &"32 bytes"));
+    }
+
+    let mut array = [0; 32];
+    array.copy_from_slice(&bytes);
+    Ok(array)
+}
+
+/// Hex of the big-endian bytes, e.g. how bitcoind prints `chainwork` and block hashes.
+pub mod hex_be {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, s: S) -> Result<S::Ok, S::Error>
+    where
+        T: FixedBytes,
+        S: Serializer,
+    {
+        let mut bytes = value.to_le_array();
+        bytes.reverse();
+        s.serialize_str(&hex::encode(&bytes))
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: FixedBytes,
+        D: Deserializer<'de>,
+    {
+        let mut bytes = parse_hex::<D::Error>(&String::deserialize(d)?)?;
+        bytes.reverse();
+        Ok(T::from_le_array(bytes))
+    }
+}
+
+/// Hex of the little-endian bytes.
+pub mod hex_le {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, s: S) -> Result<S::Ok, S::Error>
+    where
+        T: FixedBytes,
+        S: Serializer,
+    {
+        s.serialize_str(&hex::encode(&value.to_le_array()))
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: FixedBytes,
+        D: Deserializer<'de>,
+    {
+        Ok(T::from_le_array(parse_hex::<D::Error>(
+            &String::deserialize(d)?,
+        )?))
+    }
+}
+
+/// The little-endian bytes as a 32 element tuple, so formats like bincode write them without a
+/// length prefix.
+pub mod bytes_fixed {
+    use super::*;
+    use ::serde::de::{SeqAccess, Visitor};
+    use ::serde::ser::SerializeTuple;
+
+    pub fn serialize<T, S>(value: &T, s: S) -> Result<S::Ok, S::Error>
+    where
+        T: FixedBytes,
+        S: Serializer,
+    {
+        let mut tuple = s.serialize_tuple(32)?;
+        for byte in value.to_le_array().iter() {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: FixedBytes,
+        D: Deserializer<'de>,
+    {
+        struct TupleVisitor;
+
+        impl<'de> Visitor<'de> for TupleVisitor {
+            type Value = [u8; 32];
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("32 bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = [0; 32];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+                }
+                Ok(bytes)
+            }
+        }
+
+        Ok(T::from_le_array(d.deserialize_tuple(32, TupleVisitor)?))
+    }
+}
+
+/// A [`Uint256`] as a base 10 string.
+pub mod decimal_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Uint256, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&value.to_dec_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Uint256, D::Error> {
+        Uint256::from_dec_str(&String::deserialize(d)?).map_err(D::Error::custom)
+    }
+}
+
+/// A [`Uint256`] as a plain number when it fits in a `u64`, and as a base 10 string otherwise.
+/// Deserializing accepts either, so it needs a self-describing format such as JSON.
+pub mod u64_if_fits {
+    use super::*;
+    use ::serde::de::Visitor;
+
+    pub fn serialize<S: Serializer>(value: &Uint256, s: S) -> Result<S::Ok, S::Error> {
+        match value.to_u64() {
+            Some(num) => s.serialize_u64(num),
+            None => s.serialize_str(&value.to_dec_string()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Uint256, D::Error> {
+        struct NumberVisitor;
+
+        impl<'de> Visitor<'de> for NumberVisitor {
+            type Value = Uint256;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an unsigned integer or a base 10 string")
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(Uint256::from(v))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Uint256::from_dec_str(v).map_err(E::custom)
+            }
+        }
+
+        d.deserialize_any(NumberVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlockHash, Buffer, Hash, Uint256};
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Payloads {
+        #[serde(with = "crate::serde::base64")]
+        standard: Buffer,
+        #[serde(with = "crate::serde::base64_url")]
+        url: Vec<u8>,
+        #[serde(with = "crate::serde::base32")]
+        label: Buffer,
+        #[serde(with = "crate::serde::zbase32")]
+        hash: Hash,
+    }
+
+    #[test]
+    fn test_adapters() {
+        let payloads = Payloads {
+            standard: Buffer::from(vec![0xfb, 0xff]),
+            url: vec![0xfb, 0xff],
+            label: Buffer::from(b"foobar".to_vec()),
+            hash: Hash::default(),
+        };
+
+        let json = serde_json::to_string(&payloads).unwrap();
+        assert_eq!(
+            json,
+            r#"{"standard":"+/8=","url":"-_8","label":"mzxw6ytboi","hash":"yyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyy"}"#
+        );
+        assert_eq!(serde_json::from_str::<Payloads>(&json).unwrap(), payloads);
+    }
+
+    #[test]
+    fn test_adapter_errors() {
+        let json = r#"{"standard":"+/8=","url":"-_8","label":"mzxw6ytboi","hash":"yy"}"#;
+        let error = serde_json::from_str::<Payloads>(json).unwrap_err();
+        assert!(error.to_string().contains("expected 32 bytes, got 1"));
+
+        let json = r#"{"standard":"+/8","url":"+/8","label":"","hash":""}"#;
+        assert!(serde_json::from_str::<Payloads>(json).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Header {
+        #[serde(with = "crate::serde::hex_be")]
+        hash: BlockHash,
+        #[serde(with = "crate::serde::hex_le")]
+        merkle_root: Hash,
+        #[serde(with = "crate::serde::hex_be")]
+        chainwork: Uint256,
+        #[serde(with = "crate::serde::decimal_string")]
+        work: Uint256,
+        #[serde(with = "crate::serde::u64_if_fits")]
+        small: Uint256,
+        #[serde(with = "crate::serde::u64_if_fits")]
+        large: Uint256,
+    }
+
+    #[test]
+    fn test_number_adapters() {
+        let block_hash = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let header = Header {
+            hash: block_hash.parse().unwrap(),
+            merkle_root: Hash::from_bytes([1; 32]),
+            chainwork: uint256!("0x100010001"),
+            work: uint256!("0x100010001"),
+            small: Uint256::from_u64_const(42),
+            large: Uint256::max_value(),
+        };
+
+        let json = serde_json::to_value(&header).unwrap();
+        assert_eq!(json["hash"], block_hash);
+        assert_eq!(json["merkle_root"], "01".repeat(32));
+        assert_eq!(
+            json["chainwork"],
+            "0000000000000000000000000000000000000000000000000000000100010001"
+        );
+        assert_eq!(json["work"], "4295032833");
+        assert_eq!(json["small"], 42);
+        assert_eq!(json["large"], Uint256::max_value().to_dec_string());
+
+        assert_eq!(serde_json::from_value::<Header>(json).unwrap(), header);
+    }
+
+    #[test]
+    fn test_hex_prefix() {
+        #[derive(Deserialize)]
+        struct Target {
+            #[serde(with = "crate::serde::hex_be")]
+            target: Uint256,
+        }
+
+        let json =
+            r#"{"target":"0x00000000ffff0000000000000000000000000000000000000000000000000000"}"#;
+        let target: Target = serde_json::from_str(json).unwrap();
+        assert_eq!(target.target, Uint256::from_u64_const(0xffff) << 208);
+
+        let json = r#"{"target":"0x00ff"}"#;
+        assert!(serde_json::from_str::<Target>(json).is_err());
+    }
+
+    #[test]
+    fn test_bytes_fixed() {
+        use serde_test::{assert_tokens, Token};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Fixed(#[serde(with = "crate::serde::bytes_fixed")] Uint256);
+
+        let mut tokens = vec![Token::U8(0); 35];
+        tokens[0] = Token::NewtypeStruct { name: "Fixed" };
+        tokens[1] = Token::Tuple { len: 32 };
+        tokens[2] = Token::U8(1);
+        tokens[34] = Token::TupleEnd;
+
+        assert_tokens(&Fixed(Uint256::from_u64_const(1)), &tokens);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::buf::BufWrite;
+use crate::buffer::Result;
+use crate::{CoreVarInt, Hash, Uint256, VarInt};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+//The number of bytes a value takes up when written to a Buffer, without writing it.
+pub trait EncodedLen {
+    fn encoded_len(&self) -> usize;
+}
+
+macro_rules! impl_fixed_len {
+    ($($ty:ty),*) => {
+        $(
+            impl EncodedLen for $ty {
+                fn encoded_len(&self) -> usize {
+                    core::mem::size_of::<$ty>()
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_len!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64, bool);
+
+impl EncodedLen for Hash {
+    fn encoded_len(&self) -> usize {
+        32
+    }
+}
+
+impl EncodedLen for Uint256 {
+    fn encoded_len(&self) -> usize {
+        32
+    }
+}
+
+impl EncodedLen for VarInt {
+    fn encoded_len(&self) -> usize {
+        self.encoded_size() as usize
+    }
+}
+
+impl EncodedLen for CoreVarInt {
+    fn encoded_len(&self) -> usize {
+        self.encoded_size() as usize
+    }
+}
+
+//Slices and strings count a varint length prefix, matching write_var_bytes and write_var_string.
+//write_bytes and write_str write no prefix, so their size is just len().
+impl EncodedLen for [u8] {
+    fn encoded_len(&self) -> usize {
+        VarInt::from(self.len()).encoded_len() + self.len()
+    }
+}
+
+impl EncodedLen for Vec<u8> {
+    fn encoded_len(&self) -> usize {
+        self.as_slice().encoded_len()
+    }
+}
+
+impl EncodedLen for str {
+    fn encoded_len(&self) -> usize {
+        self.as_bytes().encoded_len()
+    }
+}
+
+impl EncodedLen for String {
+    fn encoded_len(&self) -> usize {
+        self.as_bytes().encoded_len()
+    }
+}
+
+//Counts the bytes written through BufWrite without keeping them, e.g. to check a message against
+//a size limit before encoding it. Every writer a Buffer has is counted the same way, as they all
+//come from BufWrite.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeCounter {
+    size: usize,
+}
+
+impl SizeCounter {
+    pub fn new() -> Self {
+        SizeCounter::default()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl BufWrite for SizeCounter {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.size += bytes.len();
+        Ok(())
+    }
+
+    fn fill(&mut self, _value: u8, amount: usize) -> Result<()> {
+        self.size += amount;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Buffer;
+    use alloc::collections::{BTreeMap, BTreeSet};
+    use alloc::vec;
+
+    //Every writer SizeCounter implements, written once for both.
+    fn encode<W: BufWrite>(writer: &mut W) -> Result<()> {
+        let map: BTreeMap<u8, u32> = [(1, 2)].iter().cloned().collect();
+        let set: BTreeSet<i64> = [-1, 300].iter().cloned().collect();
+
+        writer.write_u32(1)?;
+        writer.write_i16_be(-2)?;
+        writer.write_var_bytes(&vec![7; 300])?;
+        writer.write_hash(Hash::from([1; 32]))?;
+        writer.write_u256(Uint256::from_u64_const(3))?;
+        writer.write_varint(0x10000)?;
+        writer.write_varint2(0x80)?;
+        writer.write_str("abc")?;
+        writer.write_var_string("abc")?;
+        writer.write_cstring("abc")?;
+        writer.fill(0, 5)?;
+        writer.write_i128(-1)?;
+        writer.write_u48_be(1)?;
+        writer.write_i24(-1)?;
+        writer.write_uleb128(300)?;
+        writer.write_zigzag(-65)?;
+        writer.write_vec(&[1u16, 2], |w, item| w.write_u16(*item))?;
+        writer.write_map(&map, |w, key, value| {
+            w.write_u8(*key)?;
+            w.write_u32(*value)
+        })?;
+        writer.write_set(&set, |w, item| w.write_sleb128(*item))
+    }
+
+    #[test]
+    fn test_size_counter() {
+        let mut buffer = Buffer::new();
+        let mut counter = SizeCounter::new();
+
+        encode(&mut buffer).unwrap();
+        encode(&mut counter).unwrap();
+        assert!(counter.write_i24(1 << 23).is_err());
+        assert!(counter.write_u24(1 << 24).is_err());
+
+        assert_eq!(counter.size(), buffer.len());
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        assert_eq!(0u16.encoded_len(), 2);
+        assert_eq!((-1i64).encoded_len(), 8);
+        assert_eq!(Hash::default().encoded_len(), 32);
+        assert_eq!(Uint256::default().encoded_len(), 32);
+        assert_eq!(VarInt::from(0xFFFFu64).encoded_len(), 3);
+        assert_eq!(vec![0u8; 0xFD].encoded_len(), 3 + 0xFD);
+        assert_eq!("hsd".encoded_len(), 4);
+        assert_eq!(String::new().encoded_len(), 1);
+
+        let mut counter = SizeCounter::new();
+        counter.write_var_string("hsd").unwrap();
+        assert_eq!(counter.size(), "hsd".encoded_len());
+        counter.write_str("hsd").unwrap();
+        assert_eq!(counter.size(), "hsd".encoded_len() + "hsd".len());
+
+        let buffer = Buffer::with_capacity("hsd".encoded_len());
+        assert!(buffer.capacity() >= 4);
+    }
+}
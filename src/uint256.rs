@@ -21,9 +21,22 @@
 //!Edits to the original Implementation -> We don't use the u128 type at all so I see no need for a
 //!macro. I'd rather have the code easier to understand for a newcomer, then have a macro that is
 //!only used for one thing.
-
+//!
+//! # Byte order
+//!
+//! Internally a `Uint256` is four little-endian `u64` limbs. Every conversion to and from bytes
+//! states its byte order in its name (`to_le_bytes`, `from_be_bytes`, `try_from_le_slice`, ...).
+//! The conversions that don't, `From<[u8; 32]>`, `ToHex` and `FromHex`, are big-endian so that
+//! they agree with `Debug` and `Display`, which print the number the way it's written.
+
+#[cfg(any(feature = "std", feature = "serialization"))]
+use crate::encoding::hex;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
 use encodings::hex::{FromHex, FromHexError, ToHex};
-use std::fmt;
 
 #[cfg(feature = "rng")]
 use rand::{thread_rng, Rng};
@@ -62,6 +75,32 @@ pub trait BitArray {
 #[repr(C)]
 pub struct Uint256(pub [u64; 4]);
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum Uint256Error {
+    InvalidLength { max: usize, got: usize },
+    InvalidCharacter { c: char, index: usize },
+    Empty,
+    Overflow,
+}
+
+impl fmt::Display for Uint256Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Uint256Error::InvalidLength { max, got } => {
+                write!(f, "Invalid Length: expected at most {max} bytes, got {got}")
+            }
+            Uint256Error::InvalidCharacter { c, index } => {
+                write!(f, "Invalid character '{c}' at position {index}")
+            }
+            Uint256Error::Empty => write!(f, "Empty String"),
+            Uint256Error::Overflow => write!(f, "Overflow"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Uint256Error {}
+
 //thing = Uint256
 //ty = u64
 //expr = 4
@@ -92,81 +131,66 @@ impl From<u64> for Uint256 {
     }
 }
 
+/// Interprets the array as a big-endian number, the same order `Debug` and `ToHex` use.
 impl From<[u8; 32]> for Uint256 {
     fn from(value: [u8; 32]) -> Uint256 {
-        let mut ret = [0; 4];
-        //TODO this might have to be reversed
-        (0..4).for_each(|i| {
-            let start = i * 8;
-            let end = 8 + i * 8;
-            let mut bytes = [0; 8];
-            bytes.copy_from_slice(&value[start..end]);
-            ret[i] = u64::from_be_bytes(bytes);
-        });
-        Uint256(ret)
+        Uint256::from_be_bytes(value)
     }
 }
 
+#[cfg(feature = "std")]
 impl FromHex for Uint256 {
     type Error = FromHexError;
 
-    fn from_hex<T: AsRef<[u8]>>(hex: T) -> std::result::Result<Self, Self::Error> {
-        let bytes = Vec::from_hex(hex)?;
-        if bytes.len() != 32 {
-            //@todo this should not return this error here. It should actually return invalid
-            //Uint256 something like that. The Hex isn't invalid length, the result from the hex
-            //is.
-            Err(FromHexError::InvalidHexLength)
-        } else {
-            let mut ret = [0; 32];
-            ret.copy_from_slice(&bytes);
-            Ok(Uint256::from(ret))
-        }
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> core::result::Result<Self, Self::Error> {
+        //@todo this should not return InvalidHexLength for a wrong number of bytes. It should
+        //actually return invalid Uint256 something like that.
+        Ok(Uint256::from_be_bytes(hex::decode_array(hex)?))
     }
 }
 
-impl ::std::ops::Index<usize> for Uint256 {
+impl ::core::ops::Index<usize> for Uint256 {
     type Output = u64;
 
     #[inline]
     fn index(&self, index: usize) -> &u64 {
-        let &Uint256(ref dat) = self;
+        let Uint256(dat) = self;
         &dat[index]
     }
 }
 
-impl ::std::ops::Index<::std::ops::Range<usize>> for Uint256 {
+impl ::core::ops::Index<::core::ops::Range<usize>> for Uint256 {
     type Output = [u64];
 
     #[inline]
-    fn index(&self, index: ::std::ops::Range<usize>) -> &[u64] {
+    fn index(&self, index: ::core::ops::Range<usize>) -> &[u64] {
         &self.0[index]
     }
 }
 
-impl ::std::ops::Index<::std::ops::RangeTo<usize>> for Uint256 {
+impl ::core::ops::Index<::core::ops::RangeTo<usize>> for Uint256 {
     type Output = [u64];
 
     #[inline]
-    fn index(&self, index: ::std::ops::RangeTo<usize>) -> &[u64] {
+    fn index(&self, index: ::core::ops::RangeTo<usize>) -> &[u64] {
         &self.0[index]
     }
 }
 
-impl ::std::ops::Index<::std::ops::RangeFrom<usize>> for Uint256 {
+impl ::core::ops::Index<::core::ops::RangeFrom<usize>> for Uint256 {
     type Output = [u64];
 
     #[inline]
-    fn index(&self, index: ::std::ops::RangeFrom<usize>) -> &[u64] {
+    fn index(&self, index: ::core::ops::RangeFrom<usize>) -> &[u64] {
         &self.0[index]
     }
 }
 
-impl ::std::ops::Index<::std::ops::RangeFull> for Uint256 {
+impl ::core::ops::Index<::core::ops::RangeFull> for Uint256 {
     type Output = [u64];
 
     #[inline]
-    fn index(&self, _: ::std::ops::RangeFull) -> &[u64] {
+    fn index(&self, _: ::core::ops::RangeFull) -> &[u64] {
         &self.0[..]
     }
 }
@@ -182,35 +206,34 @@ impl Eq for Uint256 {}
 
 impl PartialOrd for Uint256 {
     #[inline]
-    fn partial_cmp(&self, other: &Uint256) -> Option<::std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Uint256) -> Option<::core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for Uint256 {
     #[inline]
-    fn cmp(&self, other: &Uint256) -> ::std::cmp::Ordering {
+    fn cmp(&self, other: &Uint256) -> ::core::cmp::Ordering {
         // manually implement comparison to get little-endian ordering
         // (we need this for our numeric types; non-numeric ones shouldn't
         // be ordered anyway except to put them in BTrees or whatever, and
         // they don't care how we order as long as we're consistent).
         for i in 0..4 {
             if self[4 - 1 - i] < other[4 - 1 - i] {
-                return ::std::cmp::Ordering::Less;
+                return ::core::cmp::Ordering::Less;
             }
             if self[4 - 1 - i] > other[4 - 1 - i] {
-                return ::std::cmp::Ordering::Greater;
+                return ::core::cmp::Ordering::Greater;
             }
         }
-        ::std::cmp::Ordering::Equal
+        ::core::cmp::Ordering::Equal
     }
 }
 
-#[cfg_attr(feature = "clippy", allow(expl_impl_clone_on_copy))] // we don't define the `struct`, we have to explicitly impl
 impl Clone for Uint256 {
     #[inline]
     fn clone(&self) -> Uint256 {
-        Uint256::from(&self[..])
+        *self
     }
 }
 
@@ -230,14 +253,14 @@ impl Uint256 {
     #[inline]
     /// Converts the object to a raw pointer
     pub fn as_ptr(&self) -> *const u64 {
-        let &Uint256(ref dat) = self;
+        let Uint256(dat) = self;
         dat.as_ptr()
     }
 
     #[inline]
     /// Converts the object to a mutable raw pointer
     pub fn as_mut_ptr(&mut self) -> *mut u64 {
-        let &mut Uint256(ref mut dat) = self;
+        let Uint256(dat) = self;
         dat.as_mut_ptr()
     }
 
@@ -273,27 +296,78 @@ impl Uint256 {
         self.0
     }
 
+    /// Returns the number as 32 little-endian bytes.
     #[inline]
-    //Returns little endian bytes
     pub fn to_le_bytes(&self) -> [u8; 32] {
         let mut bytes = [0; 32];
-        for i in 0..4 {
-            //Ugly rewrite this code to be more efficient...
-            let le_bytes = self.0[i].to_le_bytes();
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
 
-            bytes[8 * i] = le_bytes[0];
-            bytes[1 + 8 * i] = le_bytes[1];
-            bytes[2 + 8 * i] = le_bytes[2];
-            bytes[3 + 8 * i] = le_bytes[3];
+    /// Returns the number as 32 big-endian bytes.
+    #[inline]
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0.iter().rev()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
 
-            //Second half
-            bytes[4 + 8 * i] = le_bytes[4];
-            bytes[5 + 8 * i] = le_bytes[5];
-            bytes[6 + 8 * i] = le_bytes[6];
-            bytes[7 + 8 * i] = le_bytes[7];
+    /// Creates a number from 32 little-endian bytes.
+    #[inline]
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Uint256 {
+        let mut ret = [0; 4];
+        for (limb, chunk) in ret.iter_mut().zip(bytes.chunks_exact(8)) {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
         }
+        Uint256(ret)
+    }
 
-        bytes
+    /// Creates a number from 32 big-endian bytes.
+    #[inline]
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Uint256 {
+        let mut ret = [0; 4];
+        for (limb, chunk) in ret.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_be_bytes(word);
+        }
+        Uint256(ret)
+    }
+
+    /// Creates a number from up to 32 little-endian bytes. Shorter slices are treated as if they
+    /// were zero-extended, so `[0x01]` is one.
+    pub fn try_from_le_slice(slice: &[u8]) -> core::result::Result<Uint256, Uint256Error> {
+        if slice.len() > 32 {
+            return Err(Uint256Error::InvalidLength {
+                max: 32,
+                got: slice.len(),
+            });
+        }
+
+        let mut bytes = [0; 32];
+        bytes[..slice.len()].copy_from_slice(slice);
+        Ok(Uint256::from_le_bytes(bytes))
+    }
+
+    /// Creates a number from up to 32 big-endian bytes. Shorter slices are treated as if they
+    /// were zero-extended, so `[0x01, 0x00]` is 256.
+    pub fn try_from_be_slice(slice: &[u8]) -> core::result::Result<Uint256, Uint256Error> {
+        if slice.len() > 32 {
+            return Err(Uint256Error::InvalidLength {
+                max: 32,
+                got: slice.len(),
+            });
+        }
+
+        let mut bytes = [0; 32];
+        bytes[32 - slice.len()..].copy_from_slice(slice);
+        Ok(Uint256::from_be_bytes(bytes))
     }
 
     /// The maximum value which can be inhabited by this type.
@@ -301,7 +375,7 @@ impl Uint256 {
     pub fn max_value() -> Self {
         let mut result = [0; 4];
         (0..4).for_each(|i| {
-            result[i] = u64::max_value();
+            result[i] = u64::MAX;
         });
         Uint256(result)
     }
@@ -315,21 +389,99 @@ impl Uint256 {
     /// Conversion to u32
     #[inline]
     pub fn low_u32(&self) -> u32 {
-        let &Uint256(ref arr) = self;
+        let Uint256(arr) = self;
         arr[0] as u32
     }
 
     /// Conversion to u64
     #[inline]
     pub fn low_u64(&self) -> u64 {
-        let &Uint256(ref arr) = self;
+        let Uint256(arr) = self;
         arr[0]
     }
 
+    /// Conversion to u64, if the number fits
+    #[inline]
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0[1..].iter().all(|limb| *limb == 0) {
+            Some(self.0[0])
+        } else {
+            None
+        }
+    }
+
+    /// Formats the number in base 10.
+    pub fn to_dec_string(&self) -> String {
+        //Largest power of ten that fits in a u64.
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut chunks = Vec::new();
+        let mut rest = *self;
+        loop {
+            let (quotient, remainder) = rest.div_rem_u64(CHUNK);
+            chunks.push(remainder);
+            if quotient == Uint256::zero() {
+                break;
+            }
+            rest = quotient;
+        }
+
+        let mut dec = chunks.pop().unwrap_or_default().to_string();
+        for chunk in chunks.iter().rev() {
+            dec.push_str(&format!("{:019}", chunk));
+        }
+        dec
+    }
+
+    /// Parses a base 10 number.
+    pub fn from_dec_str(dec: &str) -> core::result::Result<Uint256, Uint256Error> {
+        if dec.is_empty() {
+            return Err(Uint256Error::Empty);
+        }
+
+        let mut ret = Uint256::zero();
+        for (index, c) in dec.chars().enumerate() {
+            let digit = c
+                .to_digit(10)
+                .ok_or(Uint256Error::InvalidCharacter { c, index })?;
+            ret = ret
+                .checked_mul_add_u64(10, digit as u64)
+                .ok_or(Uint256Error::Overflow)?;
+        }
+        Ok(ret)
+    }
+
+    fn div_rem_u64(self, divisor: u64) -> (Uint256, u64) {
+        let mut ret = [0u64; 4];
+        let mut remainder: u128 = 0;
+        for i in (0..4).rev() {
+            let current = (remainder << 64) | self.0[i] as u128;
+            ret[i] = (current / divisor as u128) as u64;
+            remainder = current % divisor as u128;
+        }
+        (Uint256(ret), remainder as u64)
+    }
+
+    fn checked_mul_add_u64(self, mul: u64, add: u64) -> Option<Uint256> {
+        let mut ret = [0u64; 4];
+        let mut carry = add as u128;
+        for (limb, word) in ret.iter_mut().zip(self.0.iter()) {
+            let current = *word as u128 * mul as u128 + carry;
+            *limb = current as u64;
+            carry = current >> 64;
+        }
+
+        if carry == 0 {
+            Some(Uint256(ret))
+        } else {
+            None
+        }
+    }
+
     /// Return the least number of bits needed to represent the number
     #[inline]
     pub fn bits(&self) -> usize {
-        let &Uint256(ref arr) = self;
+        let Uint256(arr) = self;
         for i in 1..4 {
             if arr[4 - i] > 0 {
                 return (0x40 * (4 - i + 1)) - arr[4 - i].leading_zeros() as usize;
@@ -366,6 +518,51 @@ impl Uint256 {
         Some(Uint256(ret))
     }
 
+    /// Create an object from a given unsigned 64-bit integer, usable in `const` contexts.
+    #[inline]
+    pub const fn from_u64_const(init: u64) -> Uint256 {
+        Uint256([init, 0, 0, 0])
+    }
+
+    /// Create an object from its little-endian limbs, `limbs[0]` being the least significant.
+    #[inline]
+    pub const fn from_limbs(limbs: [u64; 4]) -> Uint256 {
+        Uint256(limbs)
+    }
+
+    /// Parses a big-endian hex number (the same format `Debug` prints) in a `const` context.
+    /// The `0x` prefix is optional and up to 64 digits are accepted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is empty, too long or contains a non-hex character. Inside a `const`
+    /// this is a compile error, which is what the [`uint256!`](macro@crate::uint256) macro relies on.
+    pub const fn from_hex_const(hex: &str) -> Uint256 {
+        let bytes = hex.as_bytes();
+        let mut start = 0;
+        if bytes.len() >= 2 && bytes[0] == b'0' && (bytes[1] == b'x' || bytes[1] == b'X') {
+            start = 2;
+        }
+
+        let digits = bytes.len() - start;
+        if digits == 0 {
+            panic!("uint256 literal has no hex digits");
+        }
+        if digits > 64 {
+            panic!("uint256 literal is longer than 64 hex digits");
+        }
+
+        let mut ret = [0u64; 4];
+        let mut i = 0;
+        while i < digits {
+            let nibble = crate::macros::hex_digit(bytes[bytes.len() - 1 - i]) as u64;
+            ret[i / 16] |= nibble << ((i % 16) * 4);
+            i += 1;
+        }
+
+        Uint256(ret)
+    }
+
     /// Create an object from a given signed 64-bit integer
     pub fn from_i64(init: i64) -> Option<Uint256> {
         assert!(init >= 0);
@@ -373,47 +570,28 @@ impl Uint256 {
     }
 
     /// Converts from big endian representation bytes in memory.
-    // TODO write a test for this please.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice is longer than 32 bytes.
+    #[deprecated(note = "use `try_from_be_slice` or `from_be_bytes` instead")]
     pub fn from_big_endian(slice: &[u8]) -> Self {
-        assert!(4 * 8 >= slice.len());
-        assert!(slice.len() % 8 == 0);
-        //TODO this may need to be reworked for various size arrays, test this.
-        let mut ret = [0; 4];
-        let length = slice.len() / 8;
-        //TODO this might have to be reversed
-        for i in 0..length {
-            let start = i * 8;
-            let end = 8 + i * 8;
-            let mut bytes = [0; 8];
-            bytes.copy_from_slice(&slice[start..end]);
-            ret[3 - i] = u64::from_be_bytes(bytes);
-        }
-
-        Uint256(ret)
+        Uint256::try_from_be_slice(slice).expect("slice longer than 32 bytes")
     }
 
-    //TODO this might or might not work. Needs a lot of testing here.
+    /// Converts from little endian representation bytes in memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice is longer than 32 bytes.
+    #[deprecated(note = "use `try_from_le_slice` or `from_le_bytes` instead")]
     pub fn from_bytes(slice: &[u8]) -> Self {
-        assert!(4 * 8 >= slice.len());
-        assert!(slice.len() % 8 == 0);
-        //TODO this may need to be reworked for various size arrays, test this.
-        let mut ret = [0; 4];
-        let length = slice.len() / 8;
-        //TODO this might have to be reversed
-        (0..length).for_each(|i| {
-            let start = i * 8;
-            let end = 8 + i * 8;
-            let mut bytes = [0; 8];
-            bytes.copy_from_slice(&slice[start..end]);
-            ret[i] = u64::from_le_bytes(bytes);
-        });
-
-        Uint256(ret)
+        Uint256::try_from_le_slice(slice).expect("slice longer than 32 bytes")
     }
 
     #[inline]
     pub fn increment(&mut self) {
-        let &mut Uint256(ref mut arr) = self;
+        let Uint256(arr) = self;
         arr[0] += 1;
         if arr[0] == 0 {
             arr[1] += 1;
@@ -427,7 +605,7 @@ impl Uint256 {
     }
 }
 
-impl ::std::ops::Add<Uint256> for Uint256 {
+impl ::core::ops::Add<Uint256> for Uint256 {
     type Output = Uint256;
 
     fn add(self, other: Uint256) -> Uint256 {
@@ -451,7 +629,7 @@ impl ::std::ops::Add<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::Sub<Uint256> for Uint256 {
+impl ::core::ops::Sub<Uint256> for Uint256 {
     type Output = Uint256;
 
     #[inline]
@@ -461,7 +639,7 @@ impl ::std::ops::Sub<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::Mul<Uint256> for Uint256 {
+impl ::core::ops::Mul<Uint256> for Uint256 {
     type Output = Uint256;
 
     fn mul(self, other: Uint256) -> Uint256 {
@@ -475,10 +653,29 @@ impl ::std::ops::Mul<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::Div<Uint256> for Uint256 {
+impl ::core::ops::Div<Uint256> for Uint256 {
     type Output = Uint256;
 
     fn div(self, other: Uint256) -> Uint256 {
+        self.div_rem(other).0
+    }
+}
+
+impl ::core::ops::Rem<Uint256> for Uint256 {
+    type Output = Uint256;
+
+    fn rem(self, other: Uint256) -> Uint256 {
+        self.div_rem(other).1
+    }
+}
+
+impl Uint256 {
+    /// Returns the quotient and the remainder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    pub fn div_rem(self, other: Uint256) -> (Uint256, Uint256) {
         let mut sub_copy = self;
         let mut shift_copy = other;
         let mut ret = [0u64; 4];
@@ -491,7 +688,7 @@ impl ::std::ops::Div<Uint256> for Uint256 {
 
         // Early return in case we are dividing by a larger number than us
         if my_bits < your_bits {
-            return Uint256(ret);
+            return (Uint256(ret), sub_copy);
         }
 
         // Bitwise long division
@@ -509,17 +706,17 @@ impl ::std::ops::Div<Uint256> for Uint256 {
             shift -= 1;
         }
 
-        Uint256(ret)
+        (Uint256(ret), sub_copy)
     }
 }
 
-/// Little-endian large integer type
+// Little-endian large integer type
 // impl_array_newtype!($name, u64, $n_words);
 
 impl BitArray for Uint256 {
     #[inline]
     fn bit(&self, index: usize) -> bool {
-        let &Uint256(ref arr) = self;
+        let Uint256(arr) = self;
         arr[index / 64] & (1 << (index % 64)) != 0
     }
 
@@ -530,7 +727,7 @@ impl BitArray for Uint256 {
 
     #[inline]
     fn mask(&self, n: usize) -> Uint256 {
-        let &Uint256(ref arr) = self;
+        let Uint256(arr) = self;
         let mut ret = [0; 4];
         for i in 0..4 {
             if n >= 0x40 * (i + 1) {
@@ -545,10 +742,10 @@ impl BitArray for Uint256 {
 
     #[inline]
     fn trailing_zeros(&self) -> usize {
-        let &Uint256(ref arr) = self;
-        for i in 0..(4 - 1) {
-            if arr[i] > 0 {
-                return (0x40 * i) + arr[i].trailing_zeros() as usize;
+        let Uint256(arr) = self;
+        for (i, word) in arr.iter().enumerate().take(4 - 1) {
+            if *word > 0 {
+                return (0x40 * i) + word.trailing_zeros() as usize;
             }
         }
         (0x40 * (4 - 1)) + arr[4 - 1].trailing_zeros() as usize
@@ -567,13 +764,13 @@ impl BitArray for Uint256 {
     }
 }
 
-impl ::std::default::Default for Uint256 {
+impl ::core::default::Default for Uint256 {
     fn default() -> Uint256 {
         BitArray::zero()
     }
 }
 
-impl ::std::ops::BitAnd<Uint256> for Uint256 {
+impl ::core::ops::BitAnd<Uint256> for Uint256 {
     type Output = Uint256;
 
     #[inline]
@@ -588,7 +785,7 @@ impl ::std::ops::BitAnd<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::BitXor<Uint256> for Uint256 {
+impl ::core::ops::BitXor<Uint256> for Uint256 {
     type Output = Uint256;
 
     #[inline]
@@ -603,7 +800,7 @@ impl ::std::ops::BitXor<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::BitOr<Uint256> for Uint256 {
+impl ::core::ops::BitOr<Uint256> for Uint256 {
     type Output = Uint256;
 
     #[inline]
@@ -618,7 +815,7 @@ impl ::std::ops::BitOr<Uint256> for Uint256 {
     }
 }
 
-impl ::std::ops::Not for Uint256 {
+impl ::core::ops::Not for Uint256 {
     type Output = Uint256;
 
     #[inline]
@@ -632,7 +829,7 @@ impl ::std::ops::Not for Uint256 {
     }
 }
 
-impl ::std::ops::Shl<usize> for Uint256 {
+impl ::core::ops::Shl<usize> for Uint256 {
     type Output = Uint256;
 
     fn shl(self, shift: usize) -> Uint256 {
@@ -654,7 +851,7 @@ impl ::std::ops::Shl<usize> for Uint256 {
     }
 }
 
-impl ::std::ops::Shr<usize> for Uint256 {
+impl ::core::ops::Shr<usize> for Uint256 {
     type Output = Uint256;
 
     fn shr(self, shift: usize) -> Uint256 {
@@ -674,26 +871,18 @@ impl ::std::ops::Shr<usize> for Uint256 {
     }
 }
 
-//@todo I'm going to preface this with a huge @todo and @smells. We need to thoroughly review the
-//fcuntionality and the endianness of this struct. I've been experiencing weird issues with how we
-//are printing out these strings, and it's even more relevant now that we are playing w/ Targets
-//and block hashes quite often. So Im going to have this fuction work for LE, but I want to come
-//back and review extensively.
+/// Big-endian hex without the `0x` prefix, i.e. the digits `Debug` prints.
+#[cfg(feature = "std")]
 impl ToHex for Uint256 {
     fn to_hex(&self) -> String {
-        let mut hex = "".to_owned();
-        for bytes in self.0.iter() {
-            hex.push_str(&bytes.to_le_bytes().to_vec().to_hex());
-        }
-
-        hex
+        hex::encode(&self.to_be_bytes())
     }
 }
 
 //TODO convert these to hex?
 impl fmt::Debug for Uint256 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let &Uint256(ref data) = self;
+        let Uint256(data) = self;
         write!(f, "0x")?;
         for ch in data.iter().rev() {
             write!(f, "{:016x}", ch)?;
@@ -708,11 +897,46 @@ impl fmt::Display for Uint256 {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Uint256 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Uint256(<[u64; 4]>::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <[u64; 4]>::size_hint(depth)
+    }
+}
+
+//Also built for our own tests, where proptest is a dev-dependency.
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for Uint256 {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Uint256>;
+
+    //Half the values are edge cases: 0, 1, MAX and every power of two, or one less than it.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use alloc::vec;
+        use proptest::prelude::*;
+
+        let one = Uint256::from_u64_const(1);
+        prop_oneof![
+            prop::sample::select(vec![Uint256::from_u64_const(0), one, Uint256::max_value()]),
+            (0..256usize).prop_map(move |bit| one << bit),
+            (1..256usize).prop_map(move |bit| (one << bit) - one),
+            any::<[u64; 4]>().prop_map(Uint256),
+            any::<[u64; 4]>().prop_map(Uint256),
+            any::<[u64; 4]>().prop_map(Uint256),
+        ]
+        .boxed()
+    }
+}
+
 #[cfg(feature = "serialization")]
 impl serde::Serialize for Uint256 {
-    fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
         if s.is_human_readable() {
-            s.serialize_str(&self.to_hex())
+            s.serialize_str(&hex::encode(&self.to_be_bytes()))
         } else {
             s.serialize_bytes(&self.to_le_bytes())
         }
@@ -721,33 +945,37 @@ impl serde::Serialize for Uint256 {
 
 #[cfg(feature = "serialization")]
 impl<'de> serde::Deserialize<'de> for Uint256 {
-    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Uint256, D::Error> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Uint256, D::Error> {
         if d.is_human_readable() {
             struct HexVisitor;
 
             impl<'de> serde::de::Visitor<'de> for HexVisitor {
                 type Value = Uint256;
 
-                fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     formatter.write_str("an ASCII hex string")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    if let Ok(hex) = ::std::str::from_utf8(v) {
-                        Uint256::from_hex(hex).map_err(E::custom)
+                    if let Ok(string) = core::str::from_utf8(v) {
+                        hex::decode_array(string)
+                            .map(Uint256::from_be_bytes)
+                            .map_err(E::custom)
                     } else {
-                        return Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self));
+                        Err(E::invalid_value(serde::de::Unexpected::Bytes(v), &self))
                     }
                 }
 
-                fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+                fn visit_str<E>(self, v: &str) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
-                    Uint256::from_hex(v).map_err(E::custom)
+                    hex::decode_array(v)
+                        .map(Uint256::from_be_bytes)
+                        .map_err(E::custom)
                 }
             }
 
@@ -758,11 +986,11 @@ impl<'de> serde::Deserialize<'de> for Uint256 {
             impl<'de> ::serde::de::Visitor<'de> for BytesVisitor {
                 type Value = Uint256;
 
-                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                     formatter.write_str("a bytestring")
                 }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+                fn visit_bytes<E>(self, v: &[u8]) -> core::result::Result<Self::Value, E>
                 where
                     E: ::serde::de::Error,
                 {
@@ -771,7 +999,7 @@ impl<'de> serde::Deserialize<'de> for Uint256 {
                     } else {
                         let mut ret = [0; 32];
                         ret.copy_from_slice(v);
-                        Ok(Uint256::from(ret))
+                        Ok(Uint256::from_le_bytes(ret))
                     }
                 }
             }
@@ -817,7 +1045,7 @@ mod tests {
             "0x00000000000000000000000000000000000000000000000000000000deadbeef"
         );
         assert_eq!(
-            format!("{}", Uint256::from_u64(u64::max_value()).unwrap()),
+            format!("{}", Uint256::from_u64(u64::MAX).unwrap()),
             "0x000000000000000000000000000000000000000000000000ffffffffffffffff"
         );
 
@@ -1002,12 +1230,190 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn uint256_const_test() {
+        const POW_LIMIT: Uint256 =
+            uint256!("0x00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+        const SMALL: Uint256 = uint256!("deadbeef");
+
+        assert_eq!(
+            POW_LIMIT,
+            Uint256::from_limbs([u64::MAX, u64::MAX, u64::MAX, 0x00000000ffffffff])
+        );
+        assert_eq!(SMALL, Uint256::from_u64_const(0xDEADBEEF));
+        assert_eq!(SMALL, Uint256::from_u64(0xDEADBEEF).unwrap());
+        assert_eq!(uint256!("0X1"), Uint256::one());
+        assert_eq!(
+            format!("{}", POW_LIMIT),
+            "0x00000000ffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn uint256_from_hex_const_invalid_test() {
+        Uint256::from_hex_const("0xfg");
+    }
+
+    #[test]
+    pub fn uint256_bytes_test() {
+        let num = Uint256([
+            0x0706050403020100,
+            0x0f0e0d0c0b0a0908,
+            0x1716151413121110,
+            0x1f1e1d1c1b1a1918,
+        ]);
+
+        let mut le = [0u8; 32];
+        for (i, byte) in le.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let mut be = le;
+        be.reverse();
+
+        assert_eq!(num.to_le_bytes(), le);
+        assert_eq!(num.to_be_bytes(), be);
+        assert_eq!(Uint256::from_le_bytes(le), num);
+        assert_eq!(Uint256::from_be_bytes(be), num);
+        assert_eq!(Uint256::from(be), num);
+    }
+
+    #[test]
+    pub fn uint256_slice_test() {
+        assert_eq!(Uint256::try_from_le_slice(&[]), Ok(Uint256::zero()));
+        assert_eq!(Uint256::try_from_le_slice(&[1]), Ok(Uint256::one()));
+        assert_eq!(
+            Uint256::try_from_le_slice(&[0, 1, 2]),
+            Ok(Uint256::from_u64_const(0x020100))
+        );
+        assert_eq!(
+            Uint256::try_from_be_slice(&[0, 1, 2]),
+            Ok(Uint256::from_u64_const(0x0102))
+        );
+        assert_eq!(
+            Uint256::try_from_be_slice(&[1; 9]),
+            Ok(Uint256([0x0101010101010101, 0x01, 0, 0]))
+        );
+        assert_eq!(
+            Uint256::try_from_be_slice(&[0; 33]),
+            Err(Uint256Error::InvalidLength { max: 32, got: 33 })
+        );
+        assert_eq!(
+            Uint256::try_from_le_slice(&[0; 40]),
+            Err(Uint256Error::InvalidLength { max: 32, got: 40 })
+        );
+    }
+
+    #[test]
+    pub fn uint256_decimal_test() {
+        let max = Uint256::max_value();
+        let max_dec =
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+        assert_eq!(Uint256::zero().to_dec_string(), "0");
+        assert_eq!(
+            Uint256::from_u64_const(u64::MAX).to_dec_string(),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            (Uint256::from_u64_const(u64::MAX) + Uint256::one()).to_dec_string(),
+            "18446744073709551616"
+        );
+        assert_eq!(max.to_dec_string(), max_dec);
+
+        assert_eq!(Uint256::from_dec_str(max_dec), Ok(max));
+        assert_eq!(
+            Uint256::from_dec_str("0042"),
+            Ok(Uint256::from_u64_const(42))
+        );
+        assert_eq!(
+            Uint256::from_dec_str(
+                "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+            ),
+            Err(Uint256Error::Overflow)
+        );
+        assert_eq!(
+            Uint256::from_dec_str("12a"),
+            Err(Uint256Error::InvalidCharacter { c: 'a', index: 2 })
+        );
+        assert_eq!(Uint256::from_dec_str(""), Err(Uint256Error::Empty));
+
+        assert_eq!(Uint256::from_u64_const(7).to_u64(), Some(7));
+        assert_eq!((Uint256::one() << 64).to_u64(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    pub fn uint256_hex_matches_debug_test() {
+        let num = uint256!("0x00000000ffff0000000000000000000000000000000000000000000000000001");
+
+        assert_eq!(format!("{:?}", num), format!("0x{}", num.to_hex()));
+        assert_eq!(Uint256::from_hex(num.to_hex()).unwrap(), num);
+        assert_eq!(Uint256::from(num.to_be_bytes()), num);
+    }
+
     #[cfg(feature = "rng")]
     #[test]
     pub fn uint256_random() {
         let random = Uint256::random();
         dbg!(random);
     }
+
+    mod laws {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn add_commutative(a: Uint256, b: Uint256) {
+                prop_assert_eq!(a + b, b + a);
+            }
+
+            #[test]
+            fn add_associative(a: Uint256, b: Uint256, c: Uint256) {
+                prop_assert_eq!((a + b) + c, a + (b + c));
+            }
+
+            #[test]
+            fn sub_inverse(a: Uint256, b: Uint256) {
+                prop_assert_eq!(a + b - b, a);
+                prop_assert_eq!(a - a, Uint256::zero());
+            }
+
+            #[test]
+            fn mul_commutative(a: Uint256, b: Uint256) {
+                prop_assert_eq!(a * b, b * a);
+            }
+
+            #[test]
+            fn mul_identity(a: Uint256) {
+                prop_assert_eq!(a * Uint256::one(), a);
+                prop_assert_eq!(a * Uint256::zero(), Uint256::zero());
+            }
+
+            #[test]
+            fn distributive(a: Uint256, b: Uint256, c: Uint256) {
+                prop_assert_eq!(a * (b + c), a * b + a * c);
+            }
+
+            #[test]
+            fn div_rem_identity(a: Uint256, b: Uint256) {
+                prop_assume!(b != Uint256::zero());
+                let (quotient, remainder) = a.div_rem(b);
+                prop_assert!(remainder < b);
+                prop_assert_eq!(quotient * b + remainder, a);
+                prop_assert_eq!(a / b, quotient);
+                prop_assert_eq!(a % b, remainder);
+            }
+
+            #[test]
+            fn bytes_round_trip(a: Uint256) {
+                prop_assert_eq!(Uint256::from_le_bytes(a.to_le_bytes()), a);
+                prop_assert_eq!(Uint256::from_be_bytes(a.to_be_bytes()), a);
+                prop_assert_eq!(Uint256::from_dec_str(&a.to_dec_string()).unwrap(), a);
+            }
+        }
+    }
 }
 
 //TODO add tests for serialization here.
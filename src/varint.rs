@@ -1,7 +1,79 @@
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+use crate::buffer::{BufferError, Result};
+use core::convert::TryFrom;
+use core::fmt;
+use core::num::TryFromIntError;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
+
+//CompactSize, the length prefix used by the network protocol.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default, Hash)]
 pub struct VarInt(u64);
 
 impl VarInt {
+    //Returns the encoding in the first `len` bytes of the array.
+    pub fn encode(&self) -> ([u8; 9], usize) {
+        let mut bytes = [0; 9];
+        let num = self.0;
+
+        let len = match num {
+            0..=0xFC => {
+                bytes[0] = num as u8;
+                1
+            }
+            0xFD..=0xFFFF => {
+                bytes[0] = 0xFD;
+                bytes[1..3].copy_from_slice(&(num as u16).to_le_bytes());
+                3
+            }
+            0x10000..=0xFFFFFFFF => {
+                bytes[0] = 0xFE;
+                bytes[1..5].copy_from_slice(&(num as u32).to_le_bytes());
+                5
+            }
+            _ => {
+                bytes[0] = 0xFF;
+                bytes[1..9].copy_from_slice(&num.to_le_bytes());
+                9
+            }
+        };
+
+        (bytes, len)
+    }
+
+    //Decodes from the start of the slice, returning the VarInt and the number of bytes it used.
+    pub fn decode(bytes: &[u8]) -> Result<(VarInt, usize)> {
+        let prefix = *bytes.first().ok_or(BufferError::OutOfBounds)?;
+
+        let (len, min) = match prefix {
+            0xFF => (9, 0x100000000),
+            0xFE => (5, 0x10000),
+            0xFD => (3, 0xFD),
+            num => return Ok((VarInt(num as u64), 1)),
+        };
+
+        let data = bytes.get(1..len).ok_or(BufferError::OutOfBounds)?;
+        let mut buf = [0; 8];
+        buf[..len - 1].copy_from_slice(data);
+        let num = u64::from_le_bytes(buf);
+
+        if num < min {
+            return Err(BufferError::NonMinimalVarInt);
+        }
+
+        Ok((VarInt(num), len))
+    }
+
+    pub fn checked_add(self, rhs: VarInt) -> Option<VarInt> {
+        self.0.checked_add(rhs.0).map(VarInt)
+    }
+
+    pub fn checked_sub(self, rhs: VarInt) -> Option<VarInt> {
+        self.0.checked_sub(rhs.0).map(VarInt)
+    }
+
+    pub fn checked_mul(self, rhs: VarInt) -> Option<VarInt> {
+        self.0.checked_mul(rhs.0).map(VarInt)
+    }
+
     pub fn encoded_size(&self) -> u32 {
         match self.0 {
             0..=0xFC => 1,
@@ -49,3 +121,288 @@ impl From<u64> for VarInt {
         VarInt(num)
     }
 }
+
+impl From<VarInt> for u64 {
+    fn from(num: VarInt) -> Self {
+        num.0
+    }
+}
+
+impl TryFrom<VarInt> for usize {
+    type Error = TryFromIntError;
+
+    fn try_from(num: VarInt) -> core::result::Result<Self, Self::Error> {
+        usize::try_from(num.0)
+    }
+}
+
+impl fmt::Display for VarInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VarInt {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(VarInt(u64::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u64::size_hint(depth)
+    }
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl proptest::arbitrary::Arbitrary for VarInt {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<VarInt>;
+
+    //Half the values are within one of a boundary where the encoded size changes.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use alloc::vec;
+        use proptest::prelude::*;
+
+        let boundaries = vec![0, 0xFC, 0xFFFF, 0xFFFFFFFF, u64::MAX];
+        prop_oneof![
+            (prop::sample::select(boundaries), -1i64..=1)
+                .prop_map(|(edge, delta)| VarInt(edge.wrapping_add(delta as u64))),
+            any::<u64>().prop_map(VarInt),
+        ]
+        .boxed()
+    }
+}
+
+//Same semantics as the u64 operators, including panicking on overflow in debug builds.
+macro_rules! impl_varint_op {
+    ($op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident) => {
+        impl $op for VarInt {
+            type Output = VarInt;
+
+            fn $fn(self, rhs: VarInt) -> VarInt {
+                VarInt(self.0.$fn(rhs.0))
+            }
+        }
+
+        impl $op<u64> for VarInt {
+            type Output = VarInt;
+
+            fn $fn(self, rhs: u64) -> VarInt {
+                VarInt(self.0.$fn(rhs))
+            }
+        }
+
+        impl $op_assign for VarInt {
+            fn $fn_assign(&mut self, rhs: VarInt) {
+                self.0.$fn_assign(rhs.0);
+            }
+        }
+
+        impl $op_assign<u64> for VarInt {
+            fn $fn_assign(&mut self, rhs: u64) {
+                self.0.$fn_assign(rhs);
+            }
+        }
+    };
+}
+
+impl_varint_op!(Add, add, AddAssign, add_assign);
+impl_varint_op!(Sub, sub, SubAssign, sub_assign);
+impl_varint_op!(Mul, mul, MulAssign, mul_assign);
+impl_varint_op!(Div, div, DivAssign, div_assign);
+impl_varint_op!(Rem, rem, RemAssign, rem_assign);
+
+//Serialized as a plain number, the CompactSize encoding is only used on the wire.
+#[cfg(feature = "serialization")]
+impl serde::Serialize for VarInt {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        s.serialize_u64(self.0)
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<'de> serde::Deserialize<'de> for VarInt {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<VarInt, D::Error> {
+        Ok(VarInt(u64::deserialize(d)?))
+    }
+}
+
+//Bitcoin Core's VARINT, used by its on-disk formats (chainstate, undo and the block index). Each
+//byte holds 7 bits, most significant group first, with the high bit set on every byte but the
+//last. Every continuation byte also stores one less than its value, which makes each number have
+//exactly one encoding.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct CoreVarInt(u64);
+
+impl CoreVarInt {
+    //Returns the encoding in the first `len` bytes of the array.
+    pub fn encode(&self) -> ([u8; 10], usize) {
+        let mut bytes = [0; 10];
+        let mut num = self.0;
+        let mut len = 0;
+
+        loop {
+            bytes[len] = (num & 0x7F) as u8 | if len > 0 { 0x80 } else { 0x00 };
+            if num <= 0x7F {
+                break;
+            }
+            num = (num >> 7) - 1;
+            len += 1;
+        }
+
+        bytes[..=len].reverse();
+        (bytes, len + 1)
+    }
+
+    //Decodes from the start of the slice, returning the CoreVarInt and the number of bytes it used.
+    pub fn decode(bytes: &[u8]) -> Result<(CoreVarInt, usize)> {
+        let mut bytes = bytes.iter();
+        CoreVarInt::decode_with(|| bytes.next().copied().ok_or(BufferError::OutOfBounds))
+    }
+
+    //Decodes from the bytes returned by next, one at a time, so readers that can't look ahead
+    //share the same checks.
+    pub(crate) fn decode_with<F>(mut next: F) -> Result<(CoreVarInt, usize)>
+    where
+        F: FnMut() -> Result<u8>,
+    {
+        let mut num: u64 = 0;
+        let mut len = 0;
+
+        loop {
+            if num > u64::MAX >> 7 {
+                return Err(BufferError::VarIntOverflow);
+            }
+
+            let byte = next()?;
+            len += 1;
+            num = (num << 7) | (byte & 0x7F) as u64;
+
+            if byte & 0x80 == 0 {
+                return Ok((CoreVarInt(num), len));
+            }
+
+            if num == u64::MAX {
+                return Err(BufferError::VarIntOverflow);
+            }
+            num += 1;
+        }
+    }
+
+    pub fn encoded_size(&self) -> u32 {
+        let mut num = self.0;
+        let mut size = 1;
+
+        while num > 0x7F {
+            num = (num >> 7) - 1;
+            size += 1;
+        }
+
+        size
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.0
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u8> for CoreVarInt {
+    fn from(num: u8) -> Self {
+        CoreVarInt(num as u64)
+    }
+}
+
+impl From<u16> for CoreVarInt {
+    fn from(num: u16) -> Self {
+        CoreVarInt(num as u64)
+    }
+}
+
+impl From<u32> for CoreVarInt {
+    fn from(num: u32) -> Self {
+        CoreVarInt(num as u64)
+    }
+}
+
+impl From<u64> for CoreVarInt {
+    fn from(num: u64) -> Self {
+        CoreVarInt(num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_encode_decode() {
+        let vectors: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (0xFC, &[0xFC]),
+            (0xFD, &[0xFD, 0xFD, 0x00]),
+            (0xFFFF, &[0xFD, 0xFF, 0xFF]),
+            (0x10000, &[0xFE, 0x00, 0x00, 0x01, 0x00]),
+            (0xFFFFFFFF, &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF]),
+            (0x100000000, &[0xFF, 0, 0, 0, 0, 1, 0, 0, 0]),
+            (u64::MAX, &[0xFF; 9]),
+        ];
+
+        for (num, encoded) in vectors {
+            let varint = VarInt::from(*num);
+            let (bytes, len) = varint.encode();
+            assert_eq!(&bytes[..len], *encoded);
+            assert_eq!(varint.encoded_size() as usize, len);
+
+            let mut trailing = encoded.to_vec();
+            trailing.push(0xAB);
+            assert_eq!(VarInt::decode(&trailing).unwrap(), (varint, len));
+        }
+
+        assert!(matches!(
+            VarInt::decode(&[0xFD, 0xFC, 0x00]),
+            Err(BufferError::NonMinimalVarInt)
+        ));
+        assert!(matches!(
+            VarInt::decode(&[0xFE, 0xFF, 0xFF]),
+            Err(BufferError::OutOfBounds)
+        ));
+        assert!(matches!(VarInt::decode(&[]), Err(BufferError::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_numeric() {
+        let mut num = VarInt::from(10u64);
+        num += 5;
+        num -= VarInt::from(3u64);
+
+        assert_eq!(num, VarInt::from(12u64));
+        assert_eq!(num * 2 / VarInt::from(3u64) % 5, VarInt::from(3u64));
+        assert_eq!(num.to_string(), "12");
+        assert_eq!(usize::try_from(num).unwrap(), 12);
+        assert_eq!(u64::from(num), 12);
+        assert_eq!(VarInt::from(u64::MAX).checked_add(num), None);
+        assert_eq!(VarInt::default().checked_sub(num), None);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_encode_decode(num: VarInt) {
+            let (bytes, len) = num.encode();
+            proptest::prop_assert_eq!(num.encoded_size() as usize, len);
+            proptest::prop_assert_eq!(VarInt::decode(&bytes).unwrap(), (num, len));
+        }
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn test_serde() {
+        let num = VarInt::from(0xFFFFu64);
+        assert_eq!(serde_json::to_string(&num).unwrap(), "65535");
+        assert_eq!(serde_json::from_str::<VarInt>("65535").unwrap(), num);
+    }
+}